@@ -0,0 +1,80 @@
+use sfml::system::Vector2f;
+use sfml::graphics::FloatRect;
+
+use ecs::{World, Entity};
+use grid_mover::GridMover;
+use player::MoveDirection;
+
+/// Anything that moves around the maze: Pac-Men and ghosts. A read only view, the systems are what change them.
+pub trait Actor {
+    fn get_entity(&self) -> Entity;
+    /// Centre of the actor in world units
+    fn get_position(&self) -> Vector2f;
+    fn get_tile(&self) -> (i32, i32);
+    fn get_direction(&self) -> MoveDirection;
+    /// Tiles per millisecond
+    fn get_speed(&self) -> f32;
+    /// Box used for actor against actor collisions in world units, None if it doesn't collide
+    fn get_collision_box(&self, tile_size: f32) -> Option<FloatRect>;
+    fn is_ghost(&self) -> bool;
+    /// The player steering it, None for ghosts the game steers
+    fn get_slot(&self) -> Option<usize>;
+}
+
+/// An entity with a transform and a mover, seen through its components
+pub struct ActorRef<'w, 's: 'w> {
+    world: &'w World<'s>,
+    entity: Entity,
+    mover: &'w GridMover,
+    position: Vector2f
+}
+
+impl<'w, 's> ActorRef<'w, 's> {
+    /// None unless the entity has the components every actor needs
+    pub fn new(world: &'w World<'s>, entity: Entity) -> Option<ActorRef<'w, 's>> {
+        let mover = world.movers.get(entity)?;
+        let transform = world.transforms.get(entity)?;
+        Some(ActorRef {
+            world: world,
+            entity: entity,
+            mover: mover,
+            position: transform.position
+        })
+    }
+}
+
+impl<'w, 's> Actor for ActorRef<'w, 's> {
+    fn get_entity(&self) -> Entity {
+        self.entity
+    }
+
+    fn get_position(&self) -> Vector2f {
+        self.position
+    }
+
+    fn get_tile(&self) -> (i32, i32) {
+        self.mover.tile
+    }
+
+    fn get_direction(&self) -> MoveDirection {
+        self.mover.direction.clone()
+    }
+
+    fn get_speed(&self) -> f32 {
+        self.mover.speed
+    }
+
+    fn get_collision_box(&self, tile_size: f32) -> Option<FloatRect> {
+        let collider = self.world.colliders.get(self.entity)?;
+        let size = collider.size * tile_size;
+        Some(FloatRect::new(self.position.x - size / 2.0, self.position.y - size / 2.0, size, size))
+    }
+
+    fn is_ghost(&self) -> bool {
+        self.world.ghosts.get(self.entity).is_some()
+    }
+
+    fn get_slot(&self) -> Option<usize> {
+        self.world.controls.get(self.entity).map(|control| control.slot)
+    }
+}
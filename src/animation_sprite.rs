@@ -1,8 +1,9 @@
 
 
+use sfml::system::Vector2f;
 use sfml::graphics::{Sprite, IntRect, FloatRect};
 
-use game_time::GameTime;
+use game_time::{GameTime, Timer};
 use units;
 
 pub struct AnimationSprite<'s> {
@@ -11,7 +12,8 @@ pub struct AnimationSprite<'s> {
     
     current_animation_frame: usize,
     frame_delay: units::MS,
-    last_time_animation_changed: units::MS,
+    // Counts up from the last frame change, so it stops along with the game
+    animation_timer: Timer,
     looping: bool,
 }
 
@@ -23,11 +25,17 @@ impl<'s> AnimationSprite<'s> {
             
             current_animation_frame: 0,
             frame_delay: 100,
-            last_time_animation_changed: 0,
+            animation_timer: Timer::new(),
             looping: true
         }
     }
     
+    /// Scales the sprite to cover one tile
+    pub fn set_size(&mut self, tile_size: f32) {
+        let local_bounds = self.sfml_sprite.get_local_bounds();
+        self.sfml_sprite.set_scale(&Vector2f::new(tile_size / local_bounds.width, tile_size / local_bounds.height));
+    }
+    
     pub fn start_animation(&mut self) {
         self.looping = true
     }
@@ -38,18 +46,19 @@ impl<'s> AnimationSprite<'s> {
     
     pub fn reset_animation(&mut self) {
         self.current_animation_frame = 0;
-        self.last_time_animation_changed = 0;
+        self.animation_timer.reset();
     }
     
     pub fn update(&mut self, game_time: &GameTime) {
-        if game_time.start_frame_time > self.last_time_animation_changed + self.frame_delay {
+        self.animation_timer.update(game_time);
+        if self.animation_timer.get_elapsed() > self.frame_delay as units::DT {
             if self.looping {
                 self.current_animation_frame = (self.current_animation_frame + 1) % (self.animation_frames.len() * 4);
                 
                 let animation_index = AnimationSprite::ping_pong(self.current_animation_frame as i32, (self.animation_frames.len() - 1) as i32);       
                 self.sfml_sprite.set_texture_rect(&self.animation_frames[animation_index as usize]);
             }
-            self.last_time_animation_changed = game_time.start_frame_time;
+            self.animation_timer.reset();
         }
     }
     
@@ -0,0 +1,206 @@
+use std::collections::HashMap;
+
+use sfml::audio::{listener, Sound, SoundBuffer, Status};
+
+use events::{EventListener, GameEvent};
+use resources::Assets;
+
+const SOUNDS_PATH: &'static str = "res/sounds";
+// How much faster the siren gets by the time the last pellet is eaten
+const SIREN_MAX_SPEEDUP: f32 = 0.5;
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum SoundType {
+    INTRO,
+    CHOMP,
+    SIREN,
+    FRIGHTENED,
+    EATGHOST,
+    DEATH
+}
+
+const SOUND_FILES: [(SoundType, &'static str); 6] = [(SoundType::INTRO, "intro.wav"),
+                                                     (SoundType::CHOMP, "waka.wav"),
+                                                     (SoundType::SIREN, "siren.wav"),
+                                                     (SoundType::FRIGHTENED, "power_pellet.wav"),
+                                                     (SoundType::EATGHOST, "ghost_eaten.wav"),
+                                                     (SoundType::DEATH, "death.wav")];
+
+/// Owns the decoded sound data, any file that fails to load is simply left silent.
+/// Sounds are only read from loose files since SFML can't decode them from an archive here.
+pub struct SoundBank {
+    buffers: HashMap<SoundType, SoundBuffer>
+}
+
+impl SoundBank {
+    pub fn new(assets: &Assets) -> SoundBank {
+        let mut buffers = HashMap::new();
+        for &(ref sound_type, file_name) in SOUND_FILES.iter() {
+            let path = format!("{}/{}", SOUNDS_PATH, file_name);
+            let buffer = assets.resolve_file(&path).ok()
+                               .and_then(|resolved| resolved.to_str().and_then(SoundBuffer::new));
+            match buffer {
+                Some(buffer) => { buffers.insert(sound_type.clone(), buffer); },
+                None => eprintln!("Could not load sound {}, it will be silent", path)
+            }
+        }
+        SoundBank {
+            buffers: buffers
+        }
+    }
+}
+
+pub struct Audio<'s> {
+    sounds: HashMap<SoundType, Sound<'s>>,
+    muted: bool,
+    power_mode: bool,
+    dead: bool
+}
+
+impl<'s> Audio<'s> {
+    pub fn new(sound_bank: &'s SoundBank) -> Audio<'s> {
+        let mut sounds = HashMap::new();
+        for (sound_type, buffer) in sound_bank.buffers.iter() {
+            if let Some(mut sound) = Sound::new_with_buffer(buffer) {
+                sound.set_loop(*sound_type == SoundType::SIREN || *sound_type == SoundType::FRIGHTENED);
+                sounds.insert(sound_type.clone(), sound);
+            }
+        }
+
+        Audio {
+            sounds: sounds,
+            muted: false,
+            power_mode: false,
+            dead: false
+        }
+    }
+
+    /// Plays the intro tune, the siren starts once it has finished
+    pub fn play_intro(&mut self) {
+        self.dead = false;
+        self.power_mode = false;
+        self.stop(SoundType::SIREN);
+        self.stop(SoundType::FRIGHTENED);
+        self.play(SoundType::INTRO);
+    }
+
+    /// Keeps the siren running outside the intro and death tunes, `progress` is the fraction of pellets eaten
+    pub fn update(&mut self, progress: f32) {
+        // Play carries on as soon as Pac-Man respawns, the siren waits for the death tune to finish
+        if self.dead && !self.is_playing(SoundType::DEATH) {
+            self.dead = false;
+        }
+        let siren_wanted = self.is_siren_wanted();
+
+        if let Some(siren) = self.sounds.get_mut(&SoundType::SIREN) {
+            siren.set_pitch(1.0 + progress * SIREN_MAX_SPEEDUP);
+            if siren_wanted && siren.get_status() != Status::Playing {
+                siren.play();
+            }
+        }
+    }
+
+    /// Pauses whatever is playing, resuming it from the same point afterwards
+    pub fn set_paused(&mut self, paused: bool) {
+        for sound in self.sounds.values_mut() {
+            match sound.get_status() {
+                Status::Playing if paused => sound.pause(),
+                Status::Paused if !paused => sound.play(),
+                _ => { }
+            }
+        }
+    }
+
+    fn is_siren_wanted(&self) -> bool {
+        !self.dead && !self.power_mode && !self.is_playing(SoundType::INTRO)
+    }
+
+    pub fn toggle_mute(&mut self) {
+        self.muted = !self.muted;
+        listener::set_global_volume(if self.muted { 0.0 } else { 100.0 });
+    }
+
+    fn play(&mut self, sound_type: SoundType) {
+        if let Some(sound) = self.sounds.get_mut(&sound_type) {
+            sound.play();
+        }
+    }
+
+    fn stop(&mut self, sound_type: SoundType) {
+        if let Some(sound) = self.sounds.get_mut(&sound_type) {
+            sound.stop();
+        }
+    }
+
+    fn is_playing(&self, sound_type: SoundType) -> bool {
+        match self.sounds.get(&sound_type) {
+            Some(sound) => sound.get_status() == Status::Playing,
+            None => false
+        }
+    }
+}
+
+impl<'s> EventListener for Audio<'s> {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            // Let the chomp finish rather than restarting it every pellet
+            GameEvent::PelletEaten { .. } if !self.is_playing(SoundType::CHOMP) => self.play(SoundType::CHOMP),
+            GameEvent::PowerPelletEaten { .. } => {
+                self.power_mode = true;
+                self.stop(SoundType::SIREN);
+                if !self.is_playing(SoundType::FRIGHTENED) {
+                    self.play(SoundType::FRIGHTENED);
+                }
+            },
+            GameEvent::PowerPelletEnded => {
+                self.power_mode = false;
+                self.stop(SoundType::FRIGHTENED);
+            },
+            GameEvent::GhostEaten { .. } => self.play(SoundType::EATGHOST),
+            GameEvent::PlayerDied => {
+                // Dying ends the power pellet without a PowerPelletEnded
+                self.dead = true;
+                self.power_mode = false;
+                self.stop(SoundType::SIREN);
+                self.stop(SoundType::FRIGHTENED);
+                self.play(SoundType::DEATH);
+            },
+            _ => { }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn siren_stops_for_power_pellets() {
+        // Without any sound files nothing is ever playing, so only the state is left
+        let sound_bank = SoundBank { buffers: HashMap::new() };
+        let mut audio = Audio::new(&sound_bank);
+        assert!(audio.is_siren_wanted());
+
+        audio.on_event(&GameEvent::PowerPelletEaten { tile: (1, 1) });
+        audio.update(0.0);
+        assert!(!audio.is_siren_wanted());
+
+        audio.on_event(&GameEvent::PowerPelletEnded);
+        audio.update(0.0);
+        assert!(audio.is_siren_wanted());
+    }
+
+    #[test]
+    fn siren_comes_back_after_the_death_tune() {
+        // Without any sound files nothing is ever playing, so only the state is left
+        let sound_bank = SoundBank { buffers: HashMap::new() };
+        let mut audio = Audio::new(&sound_bank);
+
+        audio.on_event(&GameEvent::PowerPelletEaten { tile: (1, 1) });
+        audio.on_event(&GameEvent::PlayerDied);
+        assert!(!audio.is_siren_wanted());
+
+        audio.update(0.0);
+        assert!(audio.is_siren_wanted());
+    }
+}
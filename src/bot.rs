@@ -0,0 +1,298 @@
+use std::collections::VecDeque;
+
+use sfml::system::Vector2f;
+
+use level::Level;
+use level_object::LevelType;
+use player::MoveDirection;
+use grid_mover::GridMover;
+
+// Tiles closer than this to a dangerous ghost are avoided when pathing
+const DANGER_RADIUS: i32 = 4;
+// Frightened ghosts further away than this are not worth chasing
+const CHASE_RADIUS: i32 = 8;
+
+const DIRECTIONS: [MoveDirection; 4] = [MoveDirection::UP, MoveDirection::LEFT, MoveDirection::DOWN, MoveDirection::RIGHT];
+
+pub struct Threat {
+    pub tile: (i32, i32),
+    pub frightened: bool
+}
+
+/// Distance in tiles from every tile to the nearest ghost that isn't frightened
+pub struct DangerMap {
+    width: i32,
+    height: i32,
+    distances: Vec<i32>
+}
+
+impl DangerMap {
+    pub fn new(level: &Level, threats: &[Threat]) -> DangerMap {
+        let width = level.size.x as i32;
+        let height = level.size.y as i32;
+
+        let mut danger_map = DangerMap {
+            width: width,
+            height: height,
+            distances: vec![i32::MAX; (width * height) as usize]
+        };
+
+        let mut queue = VecDeque::new();
+        for threat in threats.iter().filter(|threat| !threat.frightened) {
+            let (x, y) = threat.tile;
+            if danger_map.in_bounds(x, y) {
+                danger_map.distances[(x + y * width) as usize] = 0;
+                queue.push_back((x, y));
+            }
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            let distance = danger_map.get_distance(x, y);
+            for direction in DIRECTIONS.iter() {
+                let (next_x, next_y) = step(x, y, direction);
+                if danger_map.in_bounds(next_x, next_y) && level.is_walkable(next_x, next_y) &&
+                   danger_map.get_distance(next_x, next_y) > distance + 1 {
+                    danger_map.distances[(next_x + next_y * width) as usize] = distance + 1;
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+        return danger_map;
+    }
+
+    pub fn get_distance(&self, x: i32, y: i32) -> i32 {
+        if !self.in_bounds(x, y) {
+            return i32::MAX;
+        }
+        self.distances[(x + y * self.width) as usize]
+    }
+
+    pub fn is_dangerous(&self, x: i32, y: i32) -> bool {
+        self.get_distance(x, y) < DANGER_RADIUS
+    }
+
+    fn in_bounds(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.width && y < self.height
+    }
+}
+
+/// Drives the player in place of the keyboard, for soak testing and attract mode
+pub struct Bot {
+    /// Tiles the bot last planned to walk, ending on its target, for the debug overlay
+    pub route: Vec<(i32, i32)>,
+    last_position: Vector2f
+}
+
+impl Bot {
+    pub fn new() -> Bot {
+        Bot {
+            route: vec![],
+            last_position: Vector2f::new(-1.0, -1.0)
+        }
+    }
+
+    /// Picks the action for this frame. Only turns at tile centres (or when stuck) so the player doesn't snag on walls.
+    pub fn get_action(&mut self, mover: &GridMover, level: &Level, threats: &[Threat]) -> MoveDirection {
+        let player_pos = mover.get_world_position(level);
+        let stuck = player_pos == self.last_position;
+        self.last_position = player_pos;
+
+        if !stuck && !Bot::is_centred_on_tile(level, player_pos) {
+            return MoveDirection::NONE;
+        }
+
+        let (x, y) = mover.tile;
+        let danger_map = DangerMap::new(level, threats);
+
+        let chase_target = |tile_x: i32, tile_y: i32, distance: i32| {
+            distance <= CHASE_RADIUS &&
+            threats.iter().any(|threat| threat.frightened && threat.tile == (tile_x, tile_y))
+        };
+        if let Some((direction, route)) = Bot::find_path(level, &danger_map, (x, y), &chase_target) {
+            self.route = route;
+            return direction;
+        }
+
+        let pellet_target = |tile_x: i32, tile_y: i32, _| {
+            let level_type = &level.get_tile(tile_x, tile_y).level_type;
+            *level_type == LevelType::PELLET || *level_type == LevelType::POWERPELLET
+        };
+        if let Some((direction, route)) = Bot::find_path(level, &danger_map, (x, y), &pellet_target) {
+            self.route = route;
+            return direction;
+        }
+
+        self.route.clear();
+        return Bot::flee(level, &danger_map, (x, y));
+    }
+
+    fn is_centred_on_tile(level: &Level, position: Vector2f) -> bool {
+        let (x, y) = level.world_to_tile(position.x, position.y);
+        let centre = level.tile_centre_to_world(x, y);
+        let tolerance = level.get_tile_size() / 8.0;
+
+        (position.x - centre.x).abs() < tolerance && (position.y - centre.y).abs() < tolerance
+    }
+
+    // Breadth first search through safe tiles, returns the first step towards the closest matching tile and the route there
+    fn find_path<F>(level: &Level, danger_map: &DangerMap, start: (i32, i32), is_target: &F) -> Option<(MoveDirection, Vec<(i32, i32)>)>
+        where F: Fn(i32, i32, i32) -> bool {
+
+        let width = level.size.x as i32;
+        let mut first_steps: Vec<Option<(MoveDirection, i32)>> = vec![None; (width * level.size.y as i32) as usize];
+        let mut parents: Vec<(i32, i32)> = vec![start; (width * level.size.y as i32) as usize];
+        let mut queue = VecDeque::new();
+
+        for direction in DIRECTIONS.iter() {
+            let (next_x, next_y) = step(start.0, start.1, direction);
+            if Bot::is_passable(level, danger_map, next_x, next_y) {
+                first_steps[(next_x + next_y * width) as usize] = Some((direction.clone(), 1));
+                queue.push_back((next_x, next_y));
+            }
+        }
+
+        while let Some((x, y)) = queue.pop_front() {
+            let (first_step, distance) = first_steps[(x + y * width) as usize].clone().unwrap();
+            if is_target(x, y, distance) {
+                let mut route = vec![(x, y)];
+                while *route.last().unwrap() != start {
+                    let (route_x, route_y) = *route.last().unwrap();
+                    route.push(parents[(route_x + route_y * width) as usize]);
+                }
+                route.reverse();
+                return Some((first_step, route));
+            }
+
+            for direction in DIRECTIONS.iter() {
+                let (next_x, next_y) = step(x, y, direction);
+                if (next_x, next_y) != start && Bot::is_passable(level, danger_map, next_x, next_y) &&
+                   first_steps[(next_x + next_y * width) as usize].is_none() {
+                    first_steps[(next_x + next_y * width) as usize] = Some((first_step.clone(), distance + 1));
+                    parents[(next_x + next_y * width) as usize] = (x, y);
+                    queue.push_back((next_x, next_y));
+                }
+            }
+        }
+        return None;
+    }
+
+    fn is_passable(level: &Level, danger_map: &DangerMap, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < level.size.x as i32 && y < level.size.y as i32 &&
+        level.is_walkable(x, y) && !danger_map.is_dangerous(x, y)
+    }
+
+    // No safe route to anything, so head to whichever neighbour is furthest from the ghosts
+    fn flee(level: &Level, danger_map: &DangerMap, start: (i32, i32)) -> MoveDirection {
+        let mut best_direction = MoveDirection::NONE;
+        let mut best_distance = danger_map.get_distance(start.0, start.1);
+
+        for direction in DIRECTIONS.iter() {
+            let (next_x, next_y) = step(start.0, start.1, direction);
+            let distance = danger_map.get_distance(next_x, next_y);
+            if level.is_walkable(next_x, next_y) && distance > best_distance {
+                best_direction = direction.clone();
+                best_distance = distance;
+            }
+        }
+        return best_direction;
+    }
+}
+
+fn step(x: i32, y: i32, direction: &MoveDirection) -> (i32, i32) {
+    match *direction {
+        MoveDirection::UP => (x, y - 1),
+        MoveDirection::DOWN => (x, y + 1),
+        MoveDirection::LEFT => (x - 1, y),
+        MoveDirection::RIGHT => (x + 1, y),
+        MoveDirection::NONE => (x, y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support;
+
+    const CORRIDOR: [&'static str; 3] = ["#########",
+                                         "#       #",
+                                         "#########"];
+
+    #[test]
+    fn danger_spreads_along_open_tiles_only() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&CORRIDOR);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        let danger_map = DangerMap::new(&level, &[Threat { tile: (2, 1), frightened: false }]);
+        assert_eq!(danger_map.get_distance(2, 1), 0);
+        assert_eq!(danger_map.get_distance(7, 1), 5);
+        assert_eq!(danger_map.get_distance(2, 0), i32::MAX);
+        assert!(danger_map.is_dangerous(5, 1));
+        assert!(!danger_map.is_dangerous(6, 1));
+    }
+
+    #[test]
+    fn danger_stops_at_the_edge_of_a_map_without_border_walls() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["....",
+                                              "...."]);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        let danger_map = DangerMap::new(&level, &[Threat { tile: (0, 0), frightened: false }]);
+        assert_eq!(danger_map.get_distance(3, 1), 4);
+        assert_eq!(danger_map.get_distance(-1, 0), i32::MAX);
+    }
+
+    #[test]
+    fn frightened_ghosts_are_not_dangerous() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&CORRIDOR);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        let danger_map = DangerMap::new(&level, &[Threat { tile: (2, 1), frightened: true }]);
+        assert!(!danger_map.is_dangerous(2, 1));
+    }
+
+    #[test]
+    fn heads_for_pellets_away_from_a_ghost() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["###########",
+                                              "#..  P  ..#",
+                                              "###########"]);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+        let mover = GridMover::new((5, 1), 0.0);
+        let mut bot = Bot::new();
+
+        let threats = [Threat { tile: (2, 1), frightened: false }];
+        assert_eq!(bot.get_action(&mover, &level, &threats), MoveDirection::RIGHT);
+        assert_eq!(bot.route.last(), Some(&(8, 1)));
+    }
+
+    #[test]
+    fn chases_a_frightened_ghost_before_pellets() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["###########",
+                                              "#..  P    #",
+                                              "###########"]);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+        let mover = GridMover::new((5, 1), 0.0);
+        let mut bot = Bot::new();
+
+        let threats = [Threat { tile: (8, 1), frightened: true }];
+        assert_eq!(bot.get_action(&mover, &level, &threats), MoveDirection::RIGHT);
+        assert_eq!(bot.route.last(), Some(&(8, 1)));
+    }
+
+    #[test]
+    fn flees_when_cornered() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&CORRIDOR);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+        let mover = GridMover::new((4, 1), 0.0);
+        let mut bot = Bot::new();
+
+        let threats = [Threat { tile: (2, 1), frightened: false }];
+        assert_eq!(bot.get_action(&mover, &level, &threats), MoveDirection::RIGHT);
+        assert!(bot.route.is_empty());
+    }
+}
@@ -0,0 +1,115 @@
+use sfml::system::Vector2f;
+use sfml::graphics::{RenderTarget, VertexArray, Vertex, PrimitiveType, Color, FloatRect};
+use sfml::traits::Drawable;
+
+use actor::Actor;
+use bot::Bot;
+use game::Game;
+use level::Level;
+use player::MoveDirection;
+use grid_mover;
+
+const GRID_ALPHA: u8 = 64;
+
+/// Draws what the simulation is doing over the top of the game, in world units
+pub struct DebugOverlay {
+    pub enabled: bool,
+    grid: VertexArray,
+    lines: VertexArray
+}
+
+impl DebugOverlay {
+    pub fn new() -> DebugOverlay {
+        DebugOverlay {
+            enabled: false,
+            grid: VertexArray::new_init(PrimitiveType::Lines, 0).unwrap(),
+            lines: VertexArray::new_init(PrimitiveType::Lines, 0).unwrap()
+        }
+    }
+
+    /// Rebuilds the overlay from the latest state. The bot's route is only meaningful while it is playing.
+    pub fn update(&mut self, game: &Game, bot: Option<&Bot>) {
+        let level = &game.level;
+        self.grid.clear();
+        append_grid(&mut self.grid, level, &Color::new_rgba(255, 255, 255, GRID_ALPHA));
+
+        self.lines.clear();
+        let tile_size = level.get_tile_size();
+        for actor in game.world.actors() {
+            if let Some(rect) = actor.get_collision_box(tile_size) {
+                append_rect_outline(&mut self.lines, &rect, &Color::green());
+            }
+        }
+        // Walls in the way of where each mover is going and where it wants to go, and the one it is stopped against
+        for (_, mover) in game.world.movers.iter() {
+            for direction in [mover.direction.clone(), mover.desired_direction.clone()].iter() {
+                let (x, y) = grid_mover::get_neighbour(mover.tile, direction);
+                if *direction != MoveDirection::NONE && !level.is_walkable(x, y) {
+                    append_tile_outline(&mut self.lines, level, x, y, &Color::yellow());
+                }
+            }
+            if let Some((x, y)) = mover.blocked_tile {
+                append_tile_outline(&mut self.lines, level, x, y, &Color::red());
+            }
+        }
+
+        for (_, brain) in game.world.brains.iter() {
+            append_tile_outline(&mut self.lines, level, brain.target.0, brain.target.1, &Color::magenta());
+        }
+
+        if let Some(bot) = bot {
+            for pair in bot.route.windows(2) {
+                let from = level.tile_centre_to_world(pair[0].0, pair[0].1);
+                let to = level.tile_centre_to_world(pair[1].0, pair[1].1);
+                self.lines.append(&Vertex::new_with_pos_color(&from, &Color::cyan()));
+                self.lines.append(&Vertex::new_with_pos_color(&to, &Color::cyan()));
+            }
+            if let Some(&(x, y)) = bot.route.last() {
+                append_tile_outline(&mut self.lines, level, x, y, &Color::cyan());
+            }
+        }
+    }
+}
+
+impl Drawable for DebugOverlay {
+    fn draw<RT: RenderTarget>(&self, target: &mut RT) {
+        if self.enabled {
+            target.draw(&self.grid);
+            target.draw(&self.lines);
+        }
+    }
+}
+
+/// Appends a line along every tile edge, to a Lines vertex array
+pub fn append_grid(vertices: &mut VertexArray, level: &Level, color: &Color) {
+    let world_rect = level.get_world_rect();
+    let tile_size = level.get_tile_size();
+
+    for x in 0..level.size.x + 1 {
+        let line_x = x as f32 * tile_size;
+        vertices.append(&Vertex::new_with_pos_color(&Vector2f::new(line_x, 0.0), color));
+        vertices.append(&Vertex::new_with_pos_color(&Vector2f::new(line_x, world_rect.height), color));
+    }
+    for y in 0..level.size.y + 1 {
+        let line_y = y as f32 * tile_size;
+        vertices.append(&Vertex::new_with_pos_color(&Vector2f::new(0.0, line_y), color));
+        vertices.append(&Vertex::new_with_pos_color(&Vector2f::new(world_rect.width, line_y), color));
+    }
+}
+
+fn append_rect_outline(vertices: &mut VertexArray, rect: &FloatRect, color: &Color) {
+    let corners = [Vector2f::new(rect.left, rect.top),
+                   Vector2f::new(rect.left + rect.width, rect.top),
+                   Vector2f::new(rect.left + rect.width, rect.top + rect.height),
+                   Vector2f::new(rect.left, rect.top + rect.height)];
+    for index in 0..corners.len() {
+        vertices.append(&Vertex::new_with_pos_color(&corners[index], color));
+        vertices.append(&Vertex::new_with_pos_color(&corners[(index + 1) % corners.len()], color));
+    }
+}
+
+fn append_tile_outline(vertices: &mut VertexArray, level: &Level, x: i32, y: i32, color: &Color) {
+    let top_left = level.tile_to_world(x, y);
+    let tile_size = level.get_tile_size();
+    append_rect_outline(vertices, &FloatRect::new(top_left.x, top_left.y, tile_size, tile_size), color);
+}
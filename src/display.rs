@@ -0,0 +1,214 @@
+use std::cmp;
+
+use sfml::system::{Vector2f, Vector2u};
+use sfml::window::{ContextSettings, VideoMode, DefaultStyle, Fullscreen};
+use sfml::graphics::{RenderWindow, RenderTexture, RenderTarget, Sprite, View, FloatRect, Color,
+                     VertexArray, Vertex, PrimitiveType};
+use sfml::traits::Drawable;
+
+use screenshot;
+use error::GameError;
+
+/// Draws one drawable over another, for overlays that share the game's world view
+pub struct Layered<'a, A: 'a, B: 'a>(pub &'a A, pub &'a B);
+
+impl<'a, A: Drawable, B: Drawable> Drawable for Layered<'a, A, B> {
+    fn draw<RT: RenderTarget>(&self, target: &mut RT) {
+        target.draw(self.0);
+        target.draw(self.1);
+    }
+}
+
+// Leave room for the taskbar and window decorations when picking the starting size
+const DESKTOP_MARGIN: u32 = 100;
+const SCANLINE_ALPHA: u8 = 96;
+
+/// Renders the game at native resolution and scales it up to the window by whole pixels
+pub struct Display {
+    native_size: Vector2u,
+    frame_texture: RenderTexture,
+    scanlines: VertexArray,
+    /// Starting window size as a multiple of the native resolution
+    window_scale: u32,
+
+    pub fullscreen: bool,
+    /// Frame rate limit applied to every window created, 0 for none
+    pub frame_cap: u32,
+    pub vsync: bool,
+    pub scanlines_enabled: bool
+}
+
+impl Display {
+    pub fn new(level_size: Vector2u, window_scale: u32) -> Result<Display, GameError> {
+        let native_size = Vector2u::new(level_size.x * super::NATIVE_TILE_SIZE, level_size.y * super::NATIVE_TILE_SIZE);
+        let frame_texture = match RenderTexture::new(native_size.x, native_size.y, false) {
+            Some(frame_texture) => frame_texture,
+            None => return Err(GameError::RenderTextureCreation { width: native_size.x, height: native_size.y })
+        };
+
+        Ok(Display {
+            native_size: native_size,
+            frame_texture: frame_texture,
+            scanlines: VertexArray::new_init(PrimitiveType::Lines, 0).unwrap(),
+            window_scale: window_scale,
+            fullscreen: false,
+            frame_cap: 0,
+            vsync: false,
+            scanlines_enabled: false
+        })
+    }
+
+    /// Resizes the native frame for a reloaded map of a different size
+    pub fn set_level_size(&mut self, window: &mut RenderWindow, level_size: Vector2u) -> Result<(), GameError> {
+        let native_size = Vector2u::new(level_size.x * super::NATIVE_TILE_SIZE, level_size.y * super::NATIVE_TILE_SIZE);
+        if native_size == self.native_size {
+            return Ok(());
+        }
+        self.frame_texture = match RenderTexture::new(native_size.x, native_size.y, false) {
+            Some(frame_texture) => frame_texture,
+            None => return Err(GameError::RenderTextureCreation { width: native_size.x, height: native_size.y })
+        };
+        self.native_size = native_size;
+
+        let window_size = window.get_size();
+        self.resize(window, window_size.x, window_size.y);
+        Ok(())
+    }
+
+    pub fn create_window(&mut self) -> Result<RenderWindow, GameError> {
+        let desktop_mode = VideoMode::get_desktop_mode();
+
+        let (mode, style) = if self.fullscreen {
+            (desktop_mode, Fullscreen)
+        } else {
+            // Start at the chosen scale, shrinking it if the window wouldn't fit on the desktop
+            let fit_scale = cmp::min(desktop_mode.width.saturating_sub(DESKTOP_MARGIN) / self.native_size.x,
+                                     desktop_mode.height.saturating_sub(DESKTOP_MARGIN) / self.native_size.y);
+            let scale = cmp::max(cmp::min(self.window_scale, fit_scale), 1);
+            (VideoMode::new_init(self.native_size.x * scale, self.native_size.y * scale, 32), DefaultStyle)
+        };
+
+        let mut window = match RenderWindow::new(mode, "Rust-Man", style, &ContextSettings::default()) {
+            Some(window) => window,
+            None => return Err(GameError::WindowCreation)
+        };
+        window.set_key_repeat_enabled(false);
+        // SFML advises against using both at once
+        if self.vsync {
+            window.set_vertical_sync_enabled(true);
+        } else if self.frame_cap > 0 {
+            window.set_framerate_limit(self.frame_cap);
+        }
+
+        let window_size = window.get_size();
+        self.resize(&mut window, window_size.x, window_size.y);
+        return Ok(window);
+    }
+
+    pub fn toggle_fullscreen(&mut self, window: &mut RenderWindow) -> Result<(), GameError> {
+        self.fullscreen = !self.fullscreen;
+        window.close();
+        *window = self.create_window()?;
+        Ok(())
+    }
+
+    /// Keeps the window's view one unit per pixel so the letterboxing maths stays simple
+    pub fn resize(&mut self, window: &mut RenderWindow, width: u32, height: u32) {
+        window.set_view(&View::new_from_rect(&FloatRect::new(0.0, 0.0, width as f32, height as f32)).unwrap());
+
+        let (scale, offset) = letterbox(self.native_size, window.get_size());
+
+        // One dark line along the bottom row of every scaled up native pixel
+        self.scanlines.clear();
+        if scale > 1 {
+            let scanline_color = Color::new_rgba(0, 0, 0, SCANLINE_ALPHA);
+            let width = (self.native_size.x * scale) as f32;
+            for row in 0..self.native_size.y {
+                let y = offset.y + ((row + 1) * scale) as f32 - 0.5;
+                self.scanlines.append(&Vertex::new_with_pos_color(&Vector2f::new(offset.x, y), &scanline_color));
+                self.scanlines.append(&Vertex::new_with_pos_color(&Vector2f::new(offset.x + width, y), &scanline_color));
+            }
+        }
+    }
+
+    /// Converts a window pixel, such as the mouse position, to world units within `world_rect`
+    pub fn window_to_world(&self, window: &RenderWindow, x: i32, y: i32, world_rect: &FloatRect) -> Vector2f {
+        window_to_world(self.native_size, window.get_size(), x, y, world_rect)
+    }
+
+    /// Draws the part of the world covered by `world_rect` to the window
+    pub fn draw<D: Drawable>(&mut self, window: &mut RenderWindow, drawable: &D, world_rect: &FloatRect) {
+        screenshot::render_to(&mut self.frame_texture, drawable, world_rect);
+
+        let (scale, offset) = letterbox(self.native_size, window.get_size());
+
+        let frame = match self.frame_texture.get_texture() {
+            Some(frame) => frame,
+            None => return
+        };
+        let mut sprite = Sprite::new_with_texture(&frame).unwrap();
+        sprite.set_scale(&Vector2f::new(scale as f32, scale as f32));
+        sprite.set_position(&offset);
+
+        window.clear(&Color::black());
+        window.draw(&sprite);
+        if self.scanlines_enabled {
+            window.draw(&self.scanlines);
+        }
+    }
+}
+
+/// The whole number scale for the native frame in a window this size, never below 1, and where the scaled
+/// frame's top left corner goes to sit in the middle
+fn letterbox(native_size: Vector2u, window_size: Vector2u) -> (u32, Vector2f) {
+    let scale = cmp::max(cmp::min(window_size.x / native_size.x, window_size.y / native_size.y), 1);
+    let offset = Vector2f::new(((window_size.x as i32 - (native_size.x * scale) as i32) / 2) as f32,
+                               ((window_size.y as i32 - (native_size.y * scale) as i32) / 2) as f32);
+    return (scale, offset);
+}
+
+fn window_to_world(native_size: Vector2u, window_size: Vector2u, x: i32, y: i32, world_rect: &FloatRect) -> Vector2f {
+    let (scale, offset) = letterbox(native_size, window_size);
+    let native_x = (x as f32 - offset.x) / scale as f32;
+    let native_y = (y as f32 - offset.y) / scale as f32;
+
+    Vector2f::new(world_rect.left + native_x * world_rect.width / native_size.x as f32,
+                  world_rect.top + native_y * world_rect.height / native_size.y as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The shipped map is 32 by 19 tiles of 8 pixels
+    const NATIVE_SIZE: Vector2u = Vector2u { x: 256, y: 152 };
+
+    #[test]
+    fn scales_by_the_largest_whole_number_that_fits() {
+        let (scale, offset) = letterbox(NATIVE_SIZE, Vector2u::new(1024, 700));
+        assert_eq!(scale, 4);
+        assert_eq!((offset.x, offset.y), (0.0, 46.0));
+
+        let (scale, offset) = letterbox(NATIVE_SIZE, Vector2u::new(1100, 1000));
+        assert_eq!(scale, 4);
+        assert_eq!((offset.x, offset.y), (38.0, 196.0));
+    }
+
+    #[test]
+    fn never_scales_below_one() {
+        let (scale, offset) = letterbox(NATIVE_SIZE, Vector2u::new(200, 100));
+        assert_eq!(scale, 1);
+        assert_eq!((offset.x, offset.y), (-28.0, -26.0));
+    }
+
+    #[test]
+    fn window_pixels_map_through_the_letterbox() {
+        let world_rect = FloatRect::new(0.0, 0.0, 256.0, 152.0);
+        let window_size = Vector2u::new(1024, 700);
+        let corner = window_to_world(NATIVE_SIZE, window_size, 0, 46, &world_rect);
+        assert_eq!((corner.x, corner.y), (0.0, 0.0));
+
+        let middle = window_to_world(NATIVE_SIZE, window_size, 512, 46 + 304, &world_rect);
+        assert_eq!((middle.x, middle.y), (128.0, 76.0));
+    }
+}
@@ -0,0 +1,182 @@
+use sfml::system::Vector2f;
+use sfml::graphics::{RenderTarget, Color};
+use sfml::traits::Drawable;
+
+use player::MoveDirection;
+use grid_mover::GridMover;
+use animation_sprite::AnimationSprite;
+use sprite_quad::SpriteQuad;
+use actor::ActorRef;
+
+/// Index of an entity, shared by all of its components
+pub type Entity = usize;
+
+/// Where an entity is and which way it faces, in world units
+#[derive(Clone)]
+pub struct Transform {
+    pub position: Vector2f,
+    /// Degrees, clockwise from facing right
+    pub rotation: f32
+}
+
+/// Box used for collisions between entities, centred on the transform
+#[derive(Clone)]
+pub struct Collider {
+    /// Width and height in tiles
+    pub size: f32
+}
+
+/// Marks an entity as steered by a player, from the keyboard, a gamepad, the bot or a replay
+#[derive(Clone)]
+pub struct PlayerControl {
+    /// Direction asked for this step, NONE keeps going
+    pub action: MoveDirection,
+    /// Which player steers it, 0 for player one
+    pub slot: usize
+}
+
+/// Makes an entity a ghost, dangerous to Pac-Man unless the ghosts are frightened
+#[derive(Clone)]
+pub struct Ghost {
+    /// Where it goes back to after being eaten
+    pub home: (i32, i32),
+    /// Tint it has unless frightened
+    pub color: Color
+}
+
+/// Steers a ghost no player controls, `party::chase_system` heads it for `target`
+#[derive(Clone)]
+pub struct GhostBrain {
+    /// The tile it is heading for, picked again every update
+    pub target: (i32, i32)
+}
+
+/// One kind of component for every entity, `None` where an entity doesn't have it
+#[derive(Clone)]
+pub struct Storage<T> {
+    items: Vec<Option<T>>
+}
+
+impl<T> Storage<T> {
+    pub fn new() -> Storage<T> {
+        Storage { items: vec![] }
+    }
+
+    pub fn insert(&mut self, entity: Entity, component: T) {
+        while self.items.len() <= entity {
+            self.items.push(None);
+        }
+        self.items[entity] = Some(component);
+    }
+
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.items.get(entity).and_then(|item| item.as_ref())
+    }
+
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        self.items.get_mut(entity).and_then(|item| item.as_mut())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.items.iter().enumerate().filter_map(|(entity, item)| item.as_ref().map(|component| (entity, component)))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        self.items.iter_mut().enumerate().filter_map(|(entity, item)| item.as_mut().map(|component| (entity, component)))
+    }
+}
+
+/// Every component the simulation depends on, sprites are left out as they are rebuilt from the transforms
+#[derive(Clone)]
+pub struct WorldSnapshot {
+    entity_count: usize,
+    pub transforms: Storage<Transform>,
+    pub movers: Storage<GridMover>,
+    pub colliders: Storage<Collider>,
+    pub controls: Storage<PlayerControl>,
+    pub ghosts: Storage<Ghost>,
+    pub brains: Storage<GhostBrain>
+}
+
+/// Owns the components of every game object. Systems in `systems` run over them each fixed update.
+pub struct World<'s> {
+    entity_count: usize,
+    pub transforms: Storage<Transform>,
+    pub movers: Storage<GridMover>,
+    pub sprites: Storage<AnimationSprite<'s>>,
+    pub colliders: Storage<Collider>,
+    pub controls: Storage<PlayerControl>,
+    pub ghosts: Storage<Ghost>,
+    pub brains: Storage<GhostBrain>,
+
+    sprite_quad: SpriteQuad
+}
+
+impl<'s> World<'s> {
+    pub fn new() -> World<'s> {
+        World {
+            entity_count: 0,
+            transforms: Storage::new(),
+            movers: Storage::new(),
+            sprites: Storage::new(),
+            colliders: Storage::new(),
+            controls: Storage::new(),
+            ghosts: Storage::new(),
+            brains: Storage::new(),
+            sprite_quad: SpriteQuad::new()
+        }
+    }
+
+    /// Makes a new entity without any components
+    pub fn spawn(&mut self) -> Entity {
+        self.entity_count += 1;
+        return self.entity_count - 1;
+    }
+
+    /// Player controlled entities that aren't ghosts
+    pub fn is_pacman(&self, entity: Entity) -> bool {
+        self.controls.get(entity).is_some() && self.ghosts.get(entity).is_none()
+    }
+
+    /// Every entity that moves around the maze, in the order they were spawned
+    pub fn actors<'w>(&'w self) -> impl Iterator<Item = ActorRef<'w, 's>> + 'w {
+        (0..self.entity_count).filter_map(move |entity| ActorRef::new(self, entity))
+    }
+
+    pub fn snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            entity_count: self.entity_count,
+            transforms: self.transforms.clone(),
+            movers: self.movers.clone(),
+            colliders: self.colliders.clone(),
+            controls: self.controls.clone(),
+            ghosts: self.ghosts.clone(),
+            brains: self.brains.clone()
+        }
+    }
+
+    /// Puts the components back as they were. Sprites are kept, so the snapshot should come from a world
+    /// that spawned the same entities; run `systems::sync_system` afterwards to move them into place.
+    pub fn restore(&mut self, snapshot: WorldSnapshot) {
+        self.entity_count = snapshot.entity_count;
+        self.transforms = snapshot.transforms;
+        self.movers = snapshot.movers;
+        self.colliders = snapshot.colliders;
+        self.controls = snapshot.controls;
+        self.ghosts = snapshot.ghosts;
+        self.brains = snapshot.brains;
+    }
+}
+
+impl<'s> Drawable for World<'s> {
+    fn draw<RT: RenderTarget>(&self, target: &mut RT) {
+        // Pac-Men go on top, so they are never hidden behind a ghost or pickup
+        for &on_top in [false, true].iter() {
+            for (entity, sprite) in self.sprites.iter() {
+                if self.is_pacman(entity) == on_top {
+                    self.sprite_quad.draw_sprite(&sprite.sfml_sprite, target);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,268 @@
+use std::path::PathBuf;
+
+use sfml::system::Vector2f;
+use sfml::graphics::{RenderTarget, VertexArray, Vertex, PrimitiveType, Color};
+use sfml::traits::Drawable;
+
+use level::Level;
+use level_object::LevelType;
+use validate;
+use debug_overlay;
+
+const GRID_ALPHA: u8 = 64;
+const CURSOR_ALPHA: u8 = 96;
+const SPAWN_ALPHA: u8 = 160;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Brush {
+    Tile(LevelType),
+    PlayerSpawn
+}
+
+// Number keys pick the brush in this order
+pub const BRUSHES: [Brush; 5] = [Brush::Tile(LevelType::SPACE),
+                                 Brush::Tile(LevelType::WALL),
+                                 Brush::Tile(LevelType::PELLET),
+                                 Brush::Tile(LevelType::POWERPELLET),
+                                 Brush::PlayerSpawn];
+
+enum Change {
+    Tile { x: i32, y: i32, before: LevelType, after: LevelType },
+    PlayerSpawn { before: Option<(i32, i32)>, after: Option<(i32, i32)> }
+}
+
+/// Paints tiles into a level with the mouse. Each stroke, from pressing a button to releasing it, is one undo step.
+/// Edits only change the level in memory until it is saved, the game's map image is left alone.
+pub struct Editor {
+    pub brush: Brush,
+    /// What the map validator found after the last change
+    pub problems: Vec<String>,
+    pub unsaved: bool,
+    /// An existing file that saving will overwrite once the save key is pressed again
+    pub pending_overwrite: Option<PathBuf>,
+
+    stroke: Option<(Brush, Vec<Change>)>,
+    undo_stack: Vec<Vec<Change>>,
+    redo_stack: Vec<Vec<Change>>,
+    cursor_tile: Option<(i32, i32)>,
+    grid: VertexArray,
+    highlights: VertexArray
+}
+
+impl Editor {
+    pub fn new(level: &Level) -> Editor {
+        let mut editor = Editor {
+            brush: Brush::Tile(LevelType::WALL),
+            problems: validate::validate_level(level),
+            unsaved: false,
+            pending_overwrite: None,
+            stroke: None,
+            undo_stack: vec![],
+            redo_stack: vec![],
+            cursor_tile: None,
+            grid: VertexArray::new_init(PrimitiveType::Lines, 0).unwrap(),
+            highlights: VertexArray::new_init(PrimitiveType::Quads, 0).unwrap()
+        };
+        debug_overlay::append_grid(&mut editor.grid, level, &Color::new_rgba(255, 255, 255, GRID_ALPHA));
+        editor.update_highlights(level);
+        return editor;
+    }
+
+    /// Starts a stroke on the tile under the mouse. Erasing paints empty space whatever the brush.
+    pub fn start_stroke(&mut self, level: &mut Level, tile: (i32, i32), erase: bool) {
+        let brush = if erase { Brush::Tile(LevelType::SPACE) } else { self.brush.clone() };
+        self.stroke = Some((brush, vec![]));
+        self.paint(level, tile);
+    }
+
+    pub fn move_cursor(&mut self, level: &mut Level, tile: (i32, i32)) {
+        let in_bounds = tile.0 >= 0 && tile.1 >= 0 && tile.0 < level.size.x as i32 && tile.1 < level.size.y as i32;
+        self.cursor_tile = if in_bounds { Some(tile) } else { None };
+        self.update_highlights(level);
+        self.paint(level, tile);
+    }
+
+    pub fn end_stroke(&mut self) {
+        if let Some((_, changes)) = self.stroke.take() {
+            if !changes.is_empty() {
+                self.undo_stack.push(changes);
+                self.redo_stack.clear();
+            }
+        }
+    }
+
+    fn paint(&mut self, level: &mut Level, tile: (i32, i32)) {
+        let (x, y) = tile;
+        if x < 0 || y < 0 || x >= level.size.x as i32 || y >= level.size.y as i32 {
+            return;
+        }
+
+        let change = match self.stroke {
+            Some((Brush::Tile(ref level_type), _)) => {
+                let before = level.get_tile(x, y).level_type.clone();
+                if before == *level_type {
+                    return;
+                }
+                Change::Tile { x: x, y: y, before: before, after: level_type.clone() }
+            },
+            Some((Brush::PlayerSpawn, _)) => {
+                if level.get_player_spawn() == Some(tile) {
+                    return;
+                }
+                Change::PlayerSpawn { before: level.get_player_spawn(), after: Some(tile) }
+            },
+            None => return
+        };
+
+        Editor::apply(level, &change, false);
+        if let Some((_, ref mut changes)) = self.stroke {
+            changes.push(change);
+        }
+        self.on_changed(level);
+    }
+
+    pub fn undo(&mut self, level: &mut Level) {
+        self.end_stroke();
+        if let Some(changes) = self.undo_stack.pop() {
+            for change in changes.iter().rev() {
+                Editor::apply(level, change, true);
+            }
+            self.redo_stack.push(changes);
+            self.on_changed(level);
+        }
+    }
+
+    pub fn redo(&mut self, level: &mut Level) {
+        self.end_stroke();
+        if let Some(changes) = self.redo_stack.pop() {
+            for change in changes.iter() {
+                Editor::apply(level, change, false);
+            }
+            self.undo_stack.push(changes);
+            self.on_changed(level);
+        }
+    }
+
+    fn apply(level: &mut Level, change: &Change, undo: bool) {
+        match *change {
+            Change::Tile { x, y, ref before, ref after } => {
+                level.set_tile(x, y, if undo { before.clone() } else { after.clone() });
+            },
+            Change::PlayerSpawn { before, after } => {
+                level.set_player_spawn(if undo { before } else { after });
+            }
+        }
+    }
+
+    fn on_changed(&mut self, level: &Level) {
+        self.unsaved = true;
+        self.problems = validate::validate_level(level);
+        self.update_highlights(level);
+    }
+
+    /// One line summary for the editor's HUD
+    pub fn get_status(&self) -> String {
+        let brush = match self.brush {
+            Brush::Tile(ref level_type) => format!("{:?}", level_type),
+            Brush::PlayerSpawn => "PLAYER SPAWN".to_string()
+        };
+        let mut status = format!("EDITOR{} - Brush: {} - 1-5 brush, Z/Y undo/redo, F2 save PNG, F3 save text",
+                                 if self.unsaved { "*" } else { "" }, brush);
+        if let Some(ref path) = self.pending_overwrite {
+            status.push_str(&format!("\nPress again to overwrite {}, the old file is kept as a .bak", path.display()));
+        }
+        match self.problems.first() {
+            Some(problem) => status.push_str(&format!("\n{} problem(s): {}", self.problems.len(), problem)),
+            None => status.push_str("\nMap is valid")
+        }
+        return status;
+    }
+
+    fn update_highlights(&mut self, level: &Level) {
+        self.highlights.clear();
+        if let Some((x, y)) = level.get_player_spawn() {
+            self.append_tile_quad(level, x, y, &Color::new_rgba(255, 255, 0, SPAWN_ALPHA));
+        }
+        if let Some((x, y)) = self.cursor_tile {
+            self.append_tile_quad(level, x, y, &Color::new_rgba(255, 255, 255, CURSOR_ALPHA));
+        }
+    }
+
+    fn append_tile_quad(&mut self, level: &Level, x: i32, y: i32, color: &Color) {
+        let top_left = level.tile_to_world(x, y);
+        let tile_size = level.get_tile_size();
+        let corners = [Vector2f::new(0.0, 0.0), Vector2f::new(tile_size, 0.0),
+                       Vector2f::new(tile_size, tile_size), Vector2f::new(0.0, tile_size)];
+        for corner in corners.iter() {
+            self.highlights.append(&Vertex::new_with_pos_color(&(top_left + *corner), color));
+        }
+    }
+}
+
+impl Drawable for Editor {
+    fn draw<RT: RenderTarget>(&self, target: &mut RT) {
+        target.draw(&self.highlights);
+        target.draw(&self.grid);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use map_format;
+    use test_support::{sprite_sheet, map_image};
+
+    const ROWS: [&'static str; 3] = ["#####",
+                                     "#.P.#",
+                                     "#####"];
+
+    #[test]
+    fn a_stroke_is_one_undo_step() {
+        let sheet = sprite_sheet();
+        let mut level = Level::new_with_image(&map_image(&ROWS), &sheet);
+        let original = map_format::level_to_text(&level);
+        let mut editor = Editor::new(&level);
+
+        editor.start_stroke(&mut level, (1, 1), false);
+        editor.move_cursor(&mut level, (2, 1));
+        editor.move_cursor(&mut level, (3, 1));
+        editor.end_stroke();
+        let painted = map_format::level_to_text(&level);
+        // The spawn is drawn over whatever tile it sits on
+        assert_eq!(painted, "#####\n##P##\n#####\n");
+        assert!(editor.unsaved);
+
+        editor.undo(&mut level);
+        assert_eq!(map_format::level_to_text(&level), original);
+        editor.redo(&mut level);
+        assert_eq!(map_format::level_to_text(&level), painted);
+    }
+
+    #[test]
+    fn erasing_paints_space_and_ignores_tiles_off_the_map() {
+        let sheet = sprite_sheet();
+        let mut level = Level::new_with_image(&map_image(&ROWS), &sheet);
+        let mut editor = Editor::new(&level);
+
+        editor.start_stroke(&mut level, (1, 1), true);
+        editor.move_cursor(&mut level, (-1, 1));
+        editor.move_cursor(&mut level, (5, 1));
+        editor.end_stroke();
+        assert_eq!(map_format::level_to_text(&level), "#####\n# P.#\n#####\n");
+    }
+
+    #[test]
+    fn moving_the_spawn_can_be_undone() {
+        let sheet = sprite_sheet();
+        let mut level = Level::new_with_image(&map_image(&ROWS), &sheet);
+        let mut editor = Editor::new(&level);
+        editor.brush = Brush::PlayerSpawn;
+
+        editor.start_stroke(&mut level, (3, 1), false);
+        editor.end_stroke();
+        assert_eq!(level.get_player_spawn(), Some((3, 1)));
+
+        editor.undo(&mut level);
+        assert_eq!(level.get_player_spawn(), Some((2, 1)));
+    }
+}
@@ -0,0 +1,299 @@
+use std::io::{self, BufRead, Write};
+
+use sfml::graphics::{Image, RenderTexture};
+
+use actor::Actor;
+use game::{self, Game};
+use game_time::GameTime;
+use events::GameEvent;
+use level_object::LevelType;
+use player::{self, MoveDirection};
+use sprite_sheet::SpriteSheet;
+use screenshot;
+
+const MAX_STEPS: u32 = 10000;
+
+pub struct ActorState {
+    pub kind: &'static str,
+    pub x: f32,
+    pub y: f32,
+    pub direction: MoveDirection,
+    /// Tiles per second
+    pub speed: f32,
+    pub mode: &'static str
+}
+
+pub struct Observation {
+    pub width: u32,
+    pub height: u32,
+    /// Column major, 0 = space, 1 = wall, 2 = pellet, 3 = power pellet
+    pub tiles: Vec<u8>,
+    pub actors: Vec<ActorState>,
+    pub score: u32,
+    /// RGB bytes, row major, only filled in when frame rendering is enabled
+    pub frame: Option<Vec<u8>>
+}
+
+/// Headless wrapper around the simulation for training agents
+pub struct Env<'s> {
+    map_image: &'s Image,
+    sprite_sheet: &'s SpriteSheet,
+    game: Game<'s>,
+    game_time: GameTime,
+    frame_texture: Option<RenderTexture>,
+    steps: u32
+}
+
+impl<'s> Env<'s> {
+    pub fn new(map_image: &'s Image, sprite_sheet: &'s SpriteSheet) -> Env<'s> {
+        Env {
+            map_image: map_image,
+            sprite_sheet: sprite_sheet,
+            game: Game::new(map_image, sprite_sheet, 0),
+            game_time: GameTime::new(),
+            frame_texture: None,
+            steps: 0
+        }
+    }
+
+    pub fn set_render_frames(&mut self, enabled: bool) {
+        if !enabled {
+            self.frame_texture = None;
+        } else if self.frame_texture.is_none() {
+            let size = self.game.level.size;
+            self.frame_texture = RenderTexture::new(size.x * super::NATIVE_TILE_SIZE, size.y * super::NATIVE_TILE_SIZE, false);
+        }
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game = Game::new(self.map_image, self.sprite_sheet, seed);
+        self.game.randomise_player_position();
+        self.game_time = GameTime::new();
+        self.steps = 0;
+        return self.observe();
+    }
+
+    pub fn step(&mut self, action: MoveDirection) -> (Observation, f32, bool) {
+        self.game_time.advance(game::STEP_MS);
+        self.steps += 1;
+
+        let previous_score = self.game.score.points;
+        self.game.update(action, &self.game_time, &mut ());
+
+        let reward = (self.game.score.points as i64 - previous_score as i64) as f32;
+        let done = self.game.events.contains(&GameEvent::LevelCleared) || self.game.is_game_over() || self.steps >= MAX_STEPS;
+        return (self.observe(), reward, done);
+    }
+
+    fn observe(&mut self) -> Observation {
+        let size = self.game.level.size;
+
+        let mut tiles = Vec::with_capacity((size.x * size.y) as usize);
+        for x in 0..size.x as i32 {
+            for y in 0..size.y as i32 {
+                tiles.push(match self.game.level.get_tile(x, y).level_type {
+                    LevelType::SPACE => 0,
+                    LevelType::WALL => 1,
+                    LevelType::PELLET => 2,
+                    LevelType::POWERPELLET => 3
+                });
+            }
+        }
+
+        let tile_size = self.game.level.get_tile_size();
+        let frightened = self.game.is_frightened();
+        let actors = self.game.world.actors().map(|actor| {
+            let position = actor.get_position();
+            ActorState {
+                kind: if actor.is_ghost() { "ghost" } else { "player" },
+                x: position.x / tile_size,
+                y: position.y / tile_size,
+                direction: actor.get_direction(),
+                speed: actor.get_speed() * 1000.0,
+                mode: if actor.is_ghost() && frightened { "frightened" } else { "normal" }
+            }
+        }).collect();
+
+        Observation {
+            width: size.x,
+            height: size.y,
+            tiles: tiles,
+            actors: actors,
+            score: self.game.score.points,
+            frame: self.render_frame()
+        }
+    }
+
+    fn render_frame(&mut self) -> Option<Vec<u8>> {
+        let world_rect = self.game.level.get_world_rect();
+        let frame_texture = match self.frame_texture {
+            Some(ref mut frame_texture) => frame_texture,
+            None => return None
+        };
+
+        let image = screenshot::capture_to(frame_texture, &self.game, &world_rect)?;
+        let image_size = image.get_size();
+
+        let mut frame = Vec::with_capacity((image_size.x * image_size.y * 3) as usize);
+        for y in 0..image_size.y {
+            for x in 0..image_size.x {
+                let pixel = image.get_pixel(x, y);
+                frame.push(pixel.red);
+                frame.push(pixel.green);
+                frame.push(pixel.blue);
+            }
+        }
+        return Some(frame);
+    }
+}
+
+impl Observation {
+    pub fn to_json(&self) -> String {
+        let tiles: Vec<String> = self.tiles.iter().map(|tile| tile.to_string()).collect();
+        let actors: Vec<String> = self.actors.iter().map(|actor| {
+            format!("{{\"kind\":\"{}\",\"x\":{},\"y\":{},\"direction\":\"{:?}\",\"speed\":{},\"mode\":\"{}\"}}",
+                    actor.kind, actor.x, actor.y, actor.direction, actor.speed, actor.mode)
+        }).collect();
+        let frame = match self.frame {
+            Some(ref frame) => {
+                let hex: Vec<String> = frame.iter().map(|byte| format!("{:02x}", byte)).collect();
+                format!("\"{}\"", hex.concat())
+            },
+            None => "null".to_string()
+        };
+
+        format!("{{\"width\":{},\"height\":{},\"tiles\":[{}],\"actors\":[{}],\"score\":{},\"frame\":{}}}",
+                self.width, self.height, tiles.join(","), actors.join(","), self.score, frame)
+    }
+}
+
+/// Line based protocol for driving the environment from another process.
+/// Commands: `reset [seed]`, `step <none|up|down|left|right>`, `render <on|off>`, `quit`.
+/// Every command is answered with a single line of JSON.
+pub fn run_stdio(env: &mut Env) {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    run_protocol(env, stdin.lock(), &mut stdout.lock());
+}
+
+/// Answers the commands read from `input` on `out` until `quit` or the end of the input
+fn run_protocol<R: BufRead, W: Write>(env: &mut Env, input: R, out: &mut W) {
+    for line in input.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break
+        };
+        let mut words = line.split_whitespace();
+
+        let response = match (words.next(), words.next()) {
+            (Some("reset"), seed) => {
+                match seed.unwrap_or("0").parse::<u64>() {
+                    Ok(seed) => env.reset(seed).to_json(),
+                    Err(_) => "{\"error\":\"invalid seed\"}".to_string()
+                }
+            },
+            (Some("step"), Some(action)) => {
+                match player::parse_action(action) {
+                    Some(action) => {
+                        let (observation, reward, done) = env.step(action);
+                        format!("{{\"reward\":{},\"done\":{},\"observation\":{}}}", reward, done, observation.to_json())
+                    },
+                    None => "{\"error\":\"invalid action\"}".to_string()
+                }
+            },
+            (Some("render"), Some(toggle)) => {
+                env.set_render_frames(toggle == "on");
+                "{\"ok\":true}".to_string()
+            },
+            (Some("quit"), _) => break,
+            _ => "{\"error\":\"unknown command\"}".to_string()
+        };
+
+        if writeln!(out, "{}", response).and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support;
+
+    const MAZE: [&'static str; 3] = ["######",
+                                     "#P.  #",
+                                     "######"];
+
+    fn step_until_done(env: &mut Env, action: MoveDirection) -> f32 {
+        let mut total_reward = 0.0;
+        for _ in 0..300 {
+            let (_, reward, done) = env.step(action.clone());
+            total_reward += reward;
+            if done {
+                return total_reward;
+            }
+        }
+        panic!("the episode never finished");
+    }
+
+    #[test]
+    fn reset_observes_the_whole_maze() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&MAZE);
+        let mut env = Env::new(&image, &sprite_sheet);
+
+        let observation = env.reset(7);
+        assert_eq!((observation.width, observation.height), (6, 3));
+        assert_eq!(observation.tiles.len(), 18);
+        assert_eq!(observation.tiles[0], 1);
+        assert_eq!(observation.score, 0);
+        assert!(observation.frame.is_none());
+        assert_eq!(observation.actors.iter().filter(|actor| actor.kind == "player").count(), 1);
+        assert!(observation.actors.iter().any(|actor| actor.kind == "ghost"));
+    }
+
+    #[test]
+    fn clearing_the_level_is_rewarded_and_ends_the_episode() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&MAZE);
+        let mut env = Env::new(&image, &sprite_sheet);
+        env.game.ghost_count = 0;
+        env.game.reset_world();
+
+        assert_eq!(step_until_done(&mut env, MoveDirection::RIGHT), 10.0);
+        assert_eq!(env.game.level.get_pellet_count(), 0);
+    }
+
+    #[test]
+    fn losing_the_last_life_ends_the_episode() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&MAZE);
+        let mut env = Env::new(&image, &sprite_sheet);
+        env.game.ghost_count = 1;
+        env.game.reset_world();
+        env.game.score.lives = 1;
+
+        step_until_done(&mut env, MoveDirection::NONE);
+        assert!(env.game.is_game_over());
+    }
+
+    #[test]
+    fn protocol_answers_every_command_with_a_line_of_json() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&MAZE);
+        let mut env = Env::new(&image, &sprite_sheet);
+
+        let input = "reset 3\nstep right\nstep sideways\nrender off\nfly\nquit\nstep left\n";
+        let mut out = vec![];
+        run_protocol(&mut env, input.as_bytes(), &mut out);
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("{\"width\":6,\"height\":3,"));
+        assert!(lines[1].starts_with("{\"reward\":") && lines[1].contains(",\"observation\":{\"width\":6,"));
+        assert_eq!(lines[2], "{\"error\":\"invalid action\"}");
+        assert_eq!(lines[3], "{\"ok\":true}");
+        assert_eq!(lines[4], "{\"error\":\"unknown command\"}");
+    }
+}
@@ -0,0 +1,61 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+pub enum GameError {
+    /// A resource file couldn't be found in any of the places that were searched
+    MissingResource { path: String, searched: Vec<PathBuf> },
+    /// The file exists but SFML couldn't load it
+    InvalidResource { path: PathBuf },
+    /// Reading or writing a file failed part way
+    Io { path: PathBuf, error: io::Error },
+    WindowCreation,
+    /// An image couldn't be written, such as a rendered frame
+    ImageWrite { path: PathBuf },
+    /// A rendered frame differs from the golden image it was checked against
+    GoldenMismatch { path: PathBuf, frame: u32 },
+    RenderTextureCreation { width: u32, height: u32 },
+    InvalidArgument { name: String, value: String },
+    UnknownArgument { name: String },
+    /// A save state made on a different map, or with entities this game doesn't have
+    SaveStateMismatch,
+    /// A socket couldn't be opened, or the address to connect to couldn't be resolved
+    Network { address: String },
+    /// A rules script that couldn't be read or has a syntax error
+    Script { path: String, message: String }
+}
+
+impl fmt::Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            GameError::MissingResource { ref path, ref searched } => {
+                write!(f, "Could not find {}. Searched:", path)?;
+                for searched_path in searched.iter() {
+                    write!(f, "\n    {}", searched_path.display())?;
+                }
+                Ok(())
+            },
+            GameError::InvalidResource { ref path } => write!(f, "Could not load {}, the file may be corrupt", path.display()),
+            GameError::Io { ref path, ref error } => write!(f, "Could not read or write {}: {}", path.display(), error),
+            GameError::WindowCreation => write!(f, "Could not create the game window"),
+            GameError::ImageWrite { ref path } => write!(f, "Could not save the image to {}", path.display()),
+            GameError::GoldenMismatch { ref path, frame } => write!(f, "Frame {} does not match golden image {}", frame, path.display()),
+            GameError::RenderTextureCreation { width, height } => write!(f, "Could not create a {}x{} render texture", width, height),
+            GameError::InvalidArgument { ref name, ref value } => write!(f, "Invalid value '{}' for {}", value, name),
+            GameError::UnknownArgument { ref name } => write!(f, "Unknown argument {}, see --help", name),
+            GameError::SaveStateMismatch => write!(f, "The save state doesn't match this map"),
+            GameError::Network { ref address } => write!(f, "Could not open a connection for {}", address),
+            GameError::Script { ref path, ref message } => write!(f, "Could not load script {}: {}", path, message)
+        }
+    }
+}
+
+// Returning an error from main prints it with Debug, so keep it readable
+impl fmt::Debug for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl Error for GameError { }
@@ -0,0 +1,92 @@
+/// Something that happened during a fixed update that other systems may want to react to
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GameEvent {
+    PelletEaten { tile: (i32, i32) },
+    PowerPelletEaten { tile: (i32, i32) },
+    PowerPelletEnded,
+    GhostEaten { tile: (i32, i32), points: u32 },
+    PlayerDied,
+    /// Where the arcade would put out a bonus fruit, once 70 and again once 170 pellets are eaten.
+    /// Nothing is drawn for it yet.
+    FruitSpawned { tile: (i32, i32) },
+    LevelCleared,
+    ExtraLife
+}
+
+/// Anything that reacts to game events, such as scoring, audio or the HUD
+pub trait EventListener {
+    fn on_event(&mut self, event: &GameEvent);
+}
+
+// For updates nothing outside the game listens to
+impl EventListener for () {
+    fn on_event(&mut self, _: &GameEvent) { }
+}
+
+/// Events raised by the most recent fixed update, in the order they happened
+pub struct EventQueue {
+    events: Vec<GameEvent>
+}
+
+impl EventQueue {
+    pub fn new() -> EventQueue {
+        EventQueue {
+            events: vec![]
+        }
+    }
+
+    pub fn push(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    pub fn clear(&mut self) {
+        self.events.clear();
+    }
+
+    pub fn contains(&self, event: &GameEvent) -> bool {
+        self.events.contains(event)
+    }
+
+    /// Hands every queued event to the listener
+    pub fn dispatch<L: EventListener>(&self, listener: &mut L) {
+        for event in self.events.iter() {
+            listener.on_event(event);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Recorder {
+        events: Vec<GameEvent>
+    }
+
+    impl EventListener for Recorder {
+        fn on_event(&mut self, event: &GameEvent) {
+            self.events.push(event.clone());
+        }
+    }
+
+    #[test]
+    fn dispatches_events_in_the_order_they_happened() {
+        let mut queue = EventQueue::new();
+        queue.push(GameEvent::PelletEaten { tile: (1, 1) });
+        queue.push(GameEvent::LevelCleared);
+
+        let mut recorder = Recorder { events: vec![] };
+        queue.dispatch(&mut recorder);
+        assert_eq!(recorder.events, vec![GameEvent::PelletEaten { tile: (1, 1) }, GameEvent::LevelCleared]);
+    }
+
+    #[test]
+    fn clearing_empties_the_queue() {
+        let mut queue = EventQueue::new();
+        queue.push(GameEvent::PlayerDied);
+        assert!(queue.contains(&GameEvent::PlayerDied));
+
+        queue.clear();
+        assert!(!queue.contains(&GameEvent::PlayerDied));
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::VecDeque;
+
+use sfml::system::Vector2f;
+use sfml::graphics::{VertexArray, Vertex, Color};
+
+use units;
+
+// Ten seconds at 60 FPS, enough for the 1% lows to mean something
+const MAX_SAMPLES: usize = 600;
+// The graph only shows the most recent frames
+const GRAPH_SAMPLES: usize = 240;
+// Frames slower than this are drawn in red on the graph
+const SLOW_FRAME_MS: f32 = 1000.0 / 60.0;
+
+/// Rolling record of how long recent frames took
+pub struct FrameStats {
+    frame_times: VecDeque<units::US>
+}
+
+impl FrameStats {
+    pub fn new() -> FrameStats {
+        FrameStats {
+            frame_times: VecDeque::with_capacity(MAX_SAMPLES)
+        }
+    }
+
+    pub fn push(&mut self, frame_time: units::US) {
+        if self.frame_times.len() == MAX_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// Average frame time in milliseconds
+    pub fn get_average_ms(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        let total: units::US = self.frame_times.iter().sum();
+        total as f32 / self.frame_times.len() as f32 / 1000.0
+    }
+
+    pub fn get_average_fps(&self) -> f32 {
+        FrameStats::ms_to_fps(self.get_average_ms())
+    }
+
+    /// FPS over the slowest 1% of frames, which shows stutter the average hides
+    pub fn get_one_percent_low_fps(&self) -> f32 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        let mut sorted: Vec<units::US> = self.frame_times.iter().cloned().collect();
+        sorted.sort_by(|a, b| b.cmp(a));
+
+        let count = (sorted.len() / 100).max(1);
+        let total: units::US = sorted[..count].iter().sum();
+        FrameStats::ms_to_fps(total as f32 / count as f32 / 1000.0)
+    }
+
+    fn ms_to_fps(ms: f32) -> f32 {
+        if ms <= 0.0 { 0.0 } else { 1000.0 / ms }
+    }
+
+    /// Appends a bar per recent frame to a Lines vertex array, rising from `origin` by `pixels_per_ms`
+    pub fn append_graph(&self, vertices: &mut VertexArray, origin: Vector2f, pixels_per_ms: f32) {
+        let skip = self.frame_times.len().saturating_sub(GRAPH_SAMPLES);
+        for (index, frame_time) in self.frame_times.iter().skip(skip).enumerate() {
+            let ms = *frame_time as f32 / 1000.0;
+            let color = if ms > SLOW_FRAME_MS { Color::red() } else { Color::green() };
+            let x = origin.x + index as f32;
+            vertices.append(&Vertex::new_with_pos_color(&Vector2f::new(x, origin.y), &color));
+            vertices.append(&Vertex::new_with_pos_color(&Vector2f::new(x, origin.y - ms * pixels_per_ms), &color));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sfml::graphics::PrimitiveType;
+
+    #[test]
+    fn no_frames_means_no_rate() {
+        let stats = FrameStats::new();
+        assert_eq!(stats.get_average_fps(), 0.0);
+        assert_eq!(stats.get_one_percent_low_fps(), 0.0);
+    }
+
+    #[test]
+    fn one_slow_frame_shows_in_the_lows_but_not_the_average() {
+        let mut stats = FrameStats::new();
+        for _ in 0..99 {
+            stats.push(10_000);
+        }
+        stats.push(100_000);
+        assert!((stats.get_average_ms() - 10.9).abs() < 0.001);
+        assert!((stats.get_one_percent_low_fps() - 10.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn old_frames_are_forgotten() {
+        let mut stats = FrameStats::new();
+        stats.push(1_000_000);
+        for _ in 0..MAX_SAMPLES {
+            stats.push(20_000);
+        }
+        assert_eq!(stats.frame_times.len(), MAX_SAMPLES);
+        assert!((stats.get_average_fps() - 50.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn the_graph_shows_recent_frames_and_marks_slow_ones() {
+        let mut stats = FrameStats::new();
+        for _ in 0..GRAPH_SAMPLES {
+            stats.push(10_000);
+        }
+        stats.push(20_000);
+
+        let mut vertices = VertexArray::new_init(PrimitiveType::Lines, 0).unwrap();
+        stats.append_graph(&mut vertices, Vector2f::new(0.0, 100.0), 2.0);
+        assert_eq!(vertices.get_vertex_count() as usize, GRAPH_SAMPLES * 2);
+
+        let last_top = vertices.get_vertex(vertices.get_vertex_count() - 1);
+        assert_eq!(last_top.position.y, 60.0);
+        assert_eq!(last_top.color, Color::red());
+        assert_eq!(vertices.get_vertex(1).color, Color::green());
+    }
+}
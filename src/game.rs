@@ -0,0 +1,776 @@
+use std::mem;
+
+use sfml::system::Vector2f;
+use sfml::graphics::{Image, RenderTarget};
+use sfml::traits::Drawable;
+
+use level::Level;
+use player::{self, MoveDirection};
+use party::{self, Party};
+use bot::Threat;
+use ecs::{World, WorldSnapshot, Entity};
+use actor::Actor;
+use grid_mover::GridMover;
+use systems;
+use sprite_sheet::{SpriteType, SpriteSheet};
+use game_time::GameTime;
+use rng::Rng;
+use events::{EventQueue, EventListener, GameEvent};
+use level_object::LevelType;
+use score::Score;
+use save_state::{SaveState, MoverState};
+use script::{Script, ScriptCommand, ActorView};
+use error::GameError;
+use units;
+
+// Ghosts stay frightened a second less each level, down to the minimum
+const FRIGHTENED_MS: units::DT = 6000.0;
+const FRIGHTENED_MS_PER_LEVEL: units::DT = 1000.0;
+const MIN_FRIGHTENED_MS: units::DT = 1000.0;
+const GHOST_POINTS: u32 = 200;
+// Most ghosts a script can add, on top of any player's
+const MAX_SCRIPTED_GHOSTS: usize = 8;
+// Ghosts in the maze at the start of a normal game, like the arcade
+const GHOST_COUNT: usize = 4;
+// Pellets eaten on a level when the arcade puts out its bonus fruit
+const FRUIT_PELLETS: [usize; 2] = [70, 170];
+// Length of one fixed update, exactly 60 a second
+pub const STEP_MS: units::DT = 1000.0 / 60.0;
+
+/// What survives rebuilding the game around reloaded resources
+pub struct ReloadState {
+    score: Score,
+    rng: Rng,
+    level_number: u32,
+    tile_size: f32,
+    tiles: Vec<Vec<LevelType>>,
+    world: WorldSnapshot,
+    frightened_time: units::DT
+}
+
+/// The simulation without any window, shared by the game loop and the headless environment
+pub struct Game<'s> {
+    pub level: Level<'s>,
+    pub world: World<'s>,
+    pub player: Entity,
+    pub score: Score,
+    pub rng: Rng,
+    /// Events raised by the last update
+    pub events: EventQueue,
+    pub level_number: u32,
+    /// A second local player, None for a normal game
+    pub party: Option<Party>,
+    /// Ghosts the world is reset with, at most four. In versus the second player steers the first of them.
+    pub ghost_count: usize,
+
+    map_image: &'s Image,
+    sprite_sheet: &'s SpriteSheet,
+    initial_pellet_count: usize,
+    frightened_time: units::DT,
+    /// Custom rules, None for the built in ones
+    script: Option<Script>,
+    /// Where each ghost the script spawned starts, they come back whenever the world is reset
+    scripted_ghosts: Vec<(i32, i32)>,
+    /// Speeds set by the script, in tiles per millisecond, kept over the usual and frightened speeds
+    speed_overrides: Vec<(Entity, f32)>,
+    /// Parts of the script's view that have to be copied again in full before its next hook
+    script_tiles_stale: bool,
+    script_actors_stale: bool,
+    /// How far before and after a tile centre each Pac-Man can turn, in tiles
+    cornering: (f32, f32)
+}
+
+impl<'s> Game<'s> {
+    pub fn new(map_image: &'s Image, sprite_sheet: &'s SpriteSheet, seed: u64) -> Game<'s> {
+        let level = Level::new_with_image(map_image, sprite_sheet);
+        let mut world = World::new();
+        let cornering = (player::PRE_TURN, player::POST_TURN);
+        let player = Game::spawn_player(&mut world, &level, sprite_sheet, cornering);
+        let initial_pellet_count = level.get_pellet_count();
+        let mut game = Game {
+            level: level,
+            world: world,
+            player: player,
+            score: Score::new(),
+            rng: Rng::new(seed),
+            events: EventQueue::new(),
+            level_number: 1,
+            party: None,
+            ghost_count: GHOST_COUNT,
+            map_image: map_image,
+            sprite_sheet: sprite_sheet,
+            initial_pellet_count: initial_pellet_count,
+            frightened_time: 0.0,
+            script: None,
+            scripted_ghosts: vec![],
+            speed_overrides: vec![],
+            script_tiles_stale: true,
+            script_actors_stale: true,
+            cornering: cornering
+        };
+        game.reset_world();
+        return game;
+    }
+
+    /// The map's spawn tile, or the middle of the map if it doesn't have one
+    fn get_spawn_tile(level: &Level) -> (i32, i32) {
+        let world_rect = level.get_world_rect();
+        match level.get_player_spawn() {
+            Some(tile) => tile,
+            None => level.world_to_tile(world_rect.width / 2.0, world_rect.height / 2.0)
+        }
+    }
+
+    /// Starts the player on the spawn tile
+    fn spawn_player(world: &mut World<'s>, level: &Level<'s>, sprite_sheet: &'s SpriteSheet, cornering: (f32, f32)) -> Entity {
+        let tile = Game::get_spawn_tile(level);
+        let player = player::spawn_player(world, level, tile, sprite_sheet.generate_foreground_sprites(&SpriteType::PLAYER), 0, cornering);
+        systems::sync_system(world, level);
+        return player;
+    }
+
+    /// Starts over with the players and ghosts standing on their spawn tiles
+    pub fn reset_world(&mut self) {
+        self.world = World::new();
+        self.script_actors_stale = true;
+        self.player = Game::spawn_player(&mut self.world, &self.level, self.sprite_sheet, self.cornering);
+
+        let spawn = self.get_player_mover().tile;
+        match self.party {
+            Some(Party::COOP) => {
+                let sprite = self.sprite_sheet.generate_foreground_sprites(&SpriteType::PLAYER);
+                player::spawn_player(&mut self.world, &self.level, spawn, sprite, 1, self.cornering);
+            },
+            Some(Party::VERSUS) | None => { }
+        }
+        self.spawn_ghosts(spawn);
+        for tile in self.scripted_ghosts.clone() {
+            self.spawn_scripted_ghost(tile);
+        }
+        systems::sync_system(&mut self.world, &self.level);
+    }
+
+    /// Spreads the ghosts over the tiles furthest from Pac-Man's spawn
+    fn spawn_ghosts(&mut self, spawn: (i32, i32)) {
+        let versus = self.party == Some(Party::VERSUS);
+        let count = if versus { self.ghost_count.max(1) } else { self.ghost_count };
+        let tiles = party::get_farthest_tiles(&self.level, spawn, count);
+        for (index, (tile, color)) in tiles.into_iter().zip(party::GHOST_COLORS.iter()).enumerate() {
+            let sprite = self.sprite_sheet.generate_foreground_sprites(&SpriteType::GHOST);
+            let slot = if versus && index == 0 { Some(1) } else { None };
+            party::spawn_ghost(&mut self.world, &self.level, tile, sprite, slot, *color);
+        }
+    }
+
+    fn spawn_scripted_ghost(&mut self, tile: (i32, i32)) {
+        let sprite = self.sprite_sheet.generate_foreground_sprites(&SpriteType::GHOST);
+        party::spawn_ghost(&mut self.world, &self.level, tile, sprite, None, party::GHOST_COLORS[0]);
+    }
+
+    /// Sets how far before and after a tile centre every Pac-Man can turn, in tiles
+    pub fn set_cornering(&mut self, pre_turn: f32, post_turn: f32) {
+        self.cornering = (pre_turn, post_turn);
+        let pacmen: Vec<Entity> = self.world.movers.iter().map(|(entity, _)| entity).filter(|&entity| self.world.is_pacman(entity)).collect();
+        for entity in pacmen {
+            if let Some(mover) = self.world.movers.get_mut(entity) {
+                mover.set_cornering(pre_turn, post_turn);
+            }
+        }
+    }
+
+    /// Adds a second local player, or takes them away with None, and puts everyone back at the start
+    pub fn set_party(&mut self, party: Option<Party>) {
+        self.party = party;
+        self.reset_world();
+    }
+
+    /// Hands the rules over to a script, its set up runs straight away
+    pub fn set_script(&mut self, script: Script) {
+        self.script = Some(script);
+        self.script_tiles_stale = true;
+        self.script_actors_stale = true;
+        self.run_script(|script| script.start());
+    }
+
+    /// Runs script hooks against the game as it is now, then carries out what they asked for.
+    /// Returns None without a script.
+    fn run_script<T, F: FnOnce(&mut Script) -> T>(&mut self, hook: F) -> Option<T> {
+        let mut script = self.script.take()?;
+        self.refresh_script_view(&mut script);
+        let result = hook(&mut script);
+        let commands = script.take_commands();
+        self.script = Some(script);
+
+        if !commands.is_empty() {
+            self.script_actors_stale = true;
+        }
+        for command in commands {
+            self.apply_script_command(command);
+        }
+        return Some(result);
+    }
+
+    /// Brings the script's view up to date. Only the tiles the level changed since the last hook are copied,
+    /// the whole maze and the actors are only copied again when marked stale.
+    fn refresh_script_view(&mut self, script: &mut Script) {
+        let changed_tiles = self.level.take_changed_tiles();
+        let tiles = if self.script_tiles_stale { Some(self.get_tiles()) } else { None };
+        let actors = if self.script_actors_stale { Some(self.get_script_actors()) } else { None };
+        self.script_tiles_stale = false;
+        self.script_actors_stale = false;
+
+        let level = &self.level;
+        let (points, lives, level_number) = (self.score.points, self.score.lives, self.level_number);
+        script.update_view(|view| {
+            view.points = points;
+            view.lives = lives;
+            view.level_number = level_number;
+            match tiles {
+                Some(tiles) => view.tiles = tiles,
+                None => for (x, y) in changed_tiles {
+                    if let Some(tile) = view.tiles.get_mut(x as usize).and_then(|column| column.get_mut(y as usize)) {
+                        *tile = level.get_tile(x, y).level_type.clone();
+                    }
+                }
+            }
+            if let Some(actors) = actors {
+                view.actors = actors;
+            }
+        });
+    }
+
+    fn get_script_actors(&self) -> Vec<ActorView> {
+        self.world.actors().map(|actor| ActorView {
+            entity: actor.get_entity(),
+            tile: actor.get_tile(),
+            direction: actor.get_direction(),
+            speed: actor.get_speed() * 1000.0,
+            ghost: actor.is_ghost(),
+            slot: actor.get_slot()
+        }).collect()
+    }
+
+    // Scripts can only add to open tiles inside the maze
+    fn apply_script_command(&mut self, command: ScriptCommand) {
+        match command {
+            ScriptCommand::SpawnPickup((x, y), level_type) => {
+                if self.is_in_level(x, y) && self.level.get_tile(x, y).level_type == LevelType::SPACE {
+                    self.level.set_tile(x, y, level_type);
+                    // Counted as if it had been there from the start, so the pellets eaten never goes negative
+                    self.initial_pellet_count += 1;
+                }
+            },
+            ScriptCommand::SetSpeed(entity, speed) => {
+                if let Some(mover) = self.world.movers.get_mut(entity) {
+                    mover.speed = speed;
+                    self.speed_overrides.retain(|&(overridden, _)| overridden != entity);
+                    self.speed_overrides.push((entity, speed));
+                }
+            },
+            ScriptCommand::SpawnGhost((x, y)) => {
+                if self.is_in_level(x, y) && self.level.is_walkable(x, y) && self.scripted_ghosts.len() < MAX_SCRIPTED_GHOSTS {
+                    self.scripted_ghosts.push((x, y));
+                    self.spawn_scripted_ghost((x, y));
+                    systems::sync_system(&mut self.world, &self.level);
+                }
+            }
+        }
+    }
+
+    fn is_in_level(&self, x: i32, y: i32) -> bool {
+        x >= 0 && y >= 0 && x < self.level.size.x as i32 && y < self.level.size.y as i32
+    }
+
+    /// Sets the action of everything steered by the player in `slot`, used on the next update
+    pub fn set_action(&mut self, slot: usize, action: MoveDirection) {
+        for (_, control) in self.world.controls.iter_mut() {
+            if control.slot == slot {
+                control.action = action.clone();
+            }
+        }
+    }
+
+    /// Where the ghosts are, for the bot to avoid or chase
+    pub fn get_threats(&self) -> Vec<Threat> {
+        self.world.actors().filter(|actor| actor.is_ghost()).map(|actor| Threat {
+            tile: actor.get_tile(),
+            frightened: self.is_frightened()
+        }).collect()
+    }
+
+    /// Out of lives. With two players check after `TwoPlayer::on_player_died`, which swaps in the other player
+    /// while they have lives left.
+    pub fn is_game_over(&self) -> bool {
+        self.score.lives == 0
+    }
+
+    pub fn is_frightened(&self) -> bool {
+        self.frightened_time > 0.0
+    }
+
+    pub fn get_player_mover(&self) -> &GridMover {
+        self.world.movers.get(self.player).expect("the player always has a mover")
+    }
+
+    pub fn get_player_position(&self) -> Vector2f {
+        self.get_player_mover().get_world_position(&self.level)
+    }
+
+    /// Restarts the current level after its tiles were edited, keeping the score
+    pub fn on_level_edited(&mut self) {
+        self.initial_pellet_count = self.level.get_pellet_count();
+        self.reset_world();
+        self.frightened_time = 0.0;
+    }
+
+    /// Refills the maze and puts the player back at the start, keeping the score
+    pub fn next_level(&mut self) {
+        let tile_size = self.level.get_tile_size();
+        self.level = Level::new_with_image(self.map_image, self.sprite_sheet);
+        self.level.set_tile_size(tile_size);
+        self.script_tiles_stale = true;
+        self.initial_pellet_count = self.level.get_pellet_count();
+        self.reset_world();
+        self.level_number += 1;
+        self.frightened_time = 0.0;
+    }
+
+    /// Moves the player to a random open tile without a ghost on it, picked by the game's rng
+    pub fn randomise_player_position(&mut self) {
+        let ghost_tiles: Vec<(i32, i32)> = self.world.actors().filter(|actor| actor.is_ghost()).map(|actor| actor.get_tile()).collect();
+        let mut open_tiles = vec![];
+        for x in 0..self.level.size.x as i32 {
+            for y in 0..self.level.size.y as i32 {
+                if self.level.is_walkable(x, y) && !ghost_tiles.contains(&(x, y)) {
+                    open_tiles.push((x, y));
+                }
+            }
+        }
+        if open_tiles.is_empty() {
+            return;
+        }
+        let tile = open_tiles[self.rng.next_range(open_tiles.len() as u32) as usize];
+        if let Some(mover) = self.world.movers.get_mut(self.player) {
+            mover.set_tile(tile);
+        }
+        systems::sync_system(&mut self.world, &self.level);
+    }
+
+    /// Advances the simulation by one fixed step, filling `events` with what happened.
+    /// The score and then `listener` are handed the events, pass `&mut ()` when nothing else listens.
+    pub fn update<L: EventListener>(&mut self, action: MoveDirection, game_time: &GameTime, listener: &mut L) {
+        self.events.clear();
+        // Without a script nothing wants to know which tiles changed
+        if self.script.is_none() {
+            self.level.take_changed_tiles();
+        }
+        self.run_script(|script| script.on_tick(game_time.ticks as u32));
+
+        self.set_action(0, action);
+        let frightened = self.is_frightened();
+        party::target_system(&mut self.world, frightened);
+        self.script_ghost_targets();
+        party::chase_system(&mut self.world, &self.level);
+        systems::control_system(&mut self.world);
+        // Kept so a ghost and Pac-Man swapping tiles head on still meet
+        let previous_tiles: Vec<(Entity, (i32, i32))> = self.world.actors()
+            .map(|actor| (actor.get_entity(), actor.get_tile())).collect();
+        systems::movement_system(&mut self.world, game_time, &self.level);
+        systems::sync_system(&mut self.world, &self.level);
+        systems::animation_system(&mut self.world, game_time);
+        // The hooks from here on, and next update's on_tick, see the actors where they moved to
+        self.script_actors_stale = true;
+
+        if self.frightened_time > 0.0 {
+            self.frightened_time -= game_time.delta_time;
+            if self.frightened_time <= 0.0 {
+                self.events.push(GameEvent::PowerPelletEnded);
+            }
+        }
+
+        for tile in self.get_pacman_tiles() {
+            match self.level.eat_pellet(tile.0, tile.1) {
+                Some(LevelType::POWERPELLET) => {
+                    self.frightened_time = self.get_frightened_ms();
+                    self.events.push(GameEvent::PowerPelletEaten { tile: tile });
+                    self.on_pellet_eaten();
+                    self.run_script(|script| script.on_pellet_eaten(tile, true));
+                },
+                Some(_) => {
+                    self.events.push(GameEvent::PelletEaten { tile: tile });
+                    self.on_pellet_eaten();
+                    self.run_script(|script| script.on_pellet_eaten(tile, false));
+                },
+                None => { }
+            }
+        }
+        self.check_ghost_collisions(&previous_tiles);
+        party::frighten_system(&mut self.world, self.frightened_time > 0.0);
+        for &(entity, speed) in self.speed_overrides.iter() {
+            if let Some(mover) = self.world.movers.get_mut(entity) {
+                mover.speed = speed;
+            }
+        }
+
+        self.events.dispatch(&mut self.score);
+        if self.score.check_extra_life() {
+            self.events.push(GameEvent::ExtraLife);
+            self.score.on_event(&GameEvent::ExtraLife);
+        }
+        self.events.dispatch(listener);
+    }
+
+    fn get_frightened_ms(&self) -> units::DT {
+        (FRIGHTENED_MS - FRIGHTENED_MS_PER_LEVEL * self.level_number.saturating_sub(1) as units::DT).max(MIN_FRIGHTENED_MS)
+    }
+
+    fn get_pacman_tiles(&self) -> Vec<(i32, i32)> {
+        self.world.movers.iter().filter(|&(entity, _)| self.world.is_pacman(entity)).map(|(_, mover)| mover.tile).collect()
+    }
+
+    /// Lets the script move the targets `party::target_system` picked
+    fn script_ghost_targets(&mut self) {
+        let frightened = self.is_frightened();
+        let brains: Vec<Entity> = self.world.brains.iter().map(|(entity, _)| entity).collect();
+        if brains.is_empty() {
+            return;
+        }
+
+        let targets = self.run_script(|script| brains.iter().map(|&entity| {
+            (entity, script.get_ghost_target(entity, frightened))
+        }).collect::<Vec<_>>());
+        for (entity, target) in targets.unwrap_or_default() {
+            if let (Some(brain), Some(target)) = (self.world.brains.get_mut(entity), target) {
+                brain.target = target;
+            }
+        }
+    }
+
+    // A ghost that meets a Pac-Man is eaten while frightened, otherwise it catches them. They meet on the same tile,
+    // or when they swapped tiles this update, as at speed they can pass each other between two updates.
+    fn check_ghost_collisions(&mut self, previous_tiles: &[(Entity, (i32, i32))]) {
+        let frightened = self.frightened_time > 0.0;
+        let mut caught = false;
+
+        let previous_tile = |entity: Entity, tile: (i32, i32)| {
+            previous_tiles.iter().find(|&&(other, _)| other == entity).map(|&(_, previous)| previous).unwrap_or(tile)
+        };
+        // Where each Pac-Man is and was
+        let pacmen: Vec<((i32, i32), (i32, i32))> = self.world.actors()
+            .filter(|actor| self.world.is_pacman(actor.get_entity()))
+            .map(|actor| (actor.get_tile(), previous_tile(actor.get_entity(), actor.get_tile())))
+            .collect();
+        let colliding: Vec<Entity> = self.world.actors().filter(|actor| actor.is_ghost()).filter(|ghost| {
+            let (tile, previous) = (ghost.get_tile(), previous_tile(ghost.get_entity(), ghost.get_tile()));
+            pacmen.iter().any(|&(pacman, pacman_previous)| pacman == tile || (pacman == previous && pacman_previous == tile))
+        }).map(|ghost| ghost.get_entity()).collect();
+        for entity in colliding {
+            // The script can let a ghost and Pac-Man pass through each other
+            if !self.run_script(|script| script.on_ghost_collision(entity, frightened)).unwrap_or(true) {
+                continue;
+            }
+            let home = match self.world.ghosts.get(entity) {
+                Some(ghost) => ghost.home,
+                None => continue
+            };
+            let mover = match self.world.movers.get_mut(entity) {
+                Some(mover) => mover,
+                None => continue
+            };
+            if frightened {
+                self.events.push(GameEvent::GhostEaten { tile: mover.tile, points: GHOST_POINTS });
+                mover.set_tile(home);
+                mover.direction = MoveDirection::NONE;
+                self.script_actors_stale = true;
+            } else {
+                caught = true;
+            }
+        }
+
+        if caught {
+            self.events.push(GameEvent::PlayerDied);
+            self.frightened_time = 0.0;
+            self.reset_world();
+        }
+    }
+
+    fn on_pellet_eaten(&mut self) {
+        let eaten = self.initial_pellet_count.saturating_sub(self.level.get_pellet_count());
+        if FRUIT_PELLETS.contains(&eaten) {
+            self.events.push(GameEvent::FruitSpawned { tile: Game::get_spawn_tile(&self.level) });
+        }
+        if self.level.get_pellet_count() == 0 {
+            self.events.push(GameEvent::LevelCleared);
+        }
+    }
+
+    /// Fraction of the level's pellets that have been eaten
+    pub fn get_progress(&self) -> f32 {
+        if self.initial_pellet_count == 0 {
+            return 1.0;
+        }
+        1.0 - self.level.get_pellet_count() as f32 / self.initial_pellet_count as f32
+    }
+
+    /// Every tile of the maze, indexed by x then y
+    fn get_tiles(&self) -> Vec<Vec<LevelType>> {
+        let mut tiles = vec![];
+        for x in 0..self.level.size.x as i32 {
+            tiles.push((0..self.level.size.y as i32).map(|y| self.level.get_tile(x, y).level_type.clone()).collect());
+        }
+        return tiles;
+    }
+
+    pub fn get_save_state(&self) -> SaveState {
+        let tile_size = self.level.get_tile_size();
+        let movers = self.world.movers.iter().map(|(entity, mover)| MoverState {
+            entity: entity,
+            tile: mover.tile,
+            offset: (mover.offset.x / tile_size, mover.offset.y / tile_size),
+            direction: mover.direction.clone(),
+            desired_direction: mover.desired_direction.clone(),
+            rotation: self.world.transforms.get(entity).map(|transform| transform.rotation).unwrap_or(0.0),
+            speed: mover.speed
+        }).collect();
+
+        SaveState {
+            level_number: self.level_number,
+            points: self.score.points,
+            lives: self.score.lives,
+            extra_life_awarded: self.score.extra_life_awarded,
+            rng_state: self.rng.get_state(),
+            frightened_time: self.frightened_time,
+            initial_pellet_count: self.initial_pellet_count,
+            tiles: self.get_tiles(),
+            movers: movers,
+            scripted_ghosts: self.scripted_ghosts.clone(),
+            speed_overrides: self.speed_overrides.clone()
+        }
+    }
+
+    /// Carries on from a save state. Nothing changes if it was saved on a different map.
+    pub fn load_save_state(&mut self, state: &SaveState) -> Result<(), GameError> {
+        let same_size = state.tiles.len() == self.level.size.x as usize &&
+                        state.tiles.iter().all(|column| column.len() == self.level.size.y as usize);
+        if !same_size {
+            return Err(GameError::SaveStateMismatch);
+        }
+        // The script's ghosts are spawned again first, so their movers are there to load into.
+        // The old world goes back if the movers still don't line up.
+        let previous_world = mem::replace(&mut self.world, World::new());
+        let previous_player = self.player;
+        let previous_ghosts = mem::replace(&mut self.scripted_ghosts, state.scripted_ghosts.clone());
+        self.reset_world();
+        if state.movers.iter().any(|mover| self.world.movers.get(mover.entity).is_none()) {
+            self.world = previous_world;
+            self.player = previous_player;
+            self.scripted_ghosts = previous_ghosts;
+            return Err(GameError::SaveStateMismatch);
+        }
+        self.speed_overrides = state.speed_overrides.clone();
+
+        for (x, column) in state.tiles.iter().enumerate() {
+            for (y, level_type) in column.iter().enumerate() {
+                self.level.set_tile(x as i32, y as i32, level_type.clone());
+            }
+        }
+
+        let tile_size = self.level.get_tile_size();
+        for mover_state in state.movers.iter() {
+            if let Some(mover) = self.world.movers.get_mut(mover_state.entity) {
+                mover.set_tile(mover_state.tile);
+                mover.offset = Vector2f::new(mover_state.offset.0 * tile_size, mover_state.offset.1 * tile_size);
+                mover.direction = mover_state.direction.clone();
+                mover.desired_direction = mover_state.desired_direction.clone();
+                mover.speed = mover_state.speed;
+            }
+            if let Some(transform) = self.world.transforms.get_mut(mover_state.entity) {
+                transform.rotation = mover_state.rotation;
+            }
+        }
+        systems::sync_system(&mut self.world, &self.level);
+
+        self.level_number = state.level_number;
+        self.score.points = state.points;
+        self.score.lives = state.lives;
+        self.score.extra_life_awarded = state.extra_life_awarded;
+        self.rng = Rng::new(state.rng_state);
+        self.frightened_time = state.frightened_time;
+        self.initial_pellet_count = state.initial_pellet_count;
+        Ok(())
+    }
+
+    /// Takes everything that should carry over into a game rebuilt from reloaded resources
+    pub fn into_reload_state(self) -> ReloadState {
+        ReloadState {
+            tiles: self.get_tiles(),
+            tile_size: self.level.get_tile_size(),
+            world: self.world.snapshot(),
+            score: self.score,
+            rng: self.rng,
+            level_number: self.level_number,
+            frightened_time: self.frightened_time
+        }
+    }
+
+    /// Carries a previous game over into this one. Eaten pellets are only kept when the maze is unchanged,
+    /// and the player stays put unless the new maze walled over their tile.
+    pub fn restore_reload_state(&mut self, state: ReloadState, map_changed: bool) {
+        self.score = state.score;
+        self.rng = state.rng;
+        self.level_number = state.level_number;
+        self.frightened_time = state.frightened_time;
+        self.set_tile_size(state.tile_size);
+
+        let same_size = state.tiles.len() == self.level.size.x as usize &&
+                        state.tiles.iter().all(|column| column.len() == self.level.size.y as usize);
+        if !map_changed && same_size {
+            for (x, column) in state.tiles.into_iter().enumerate() {
+                for (y, level_type) in column.into_iter().enumerate() {
+                    self.level.set_tile(x as i32, y as i32, level_type);
+                }
+            }
+        }
+
+        let player_tile = state.world.movers.get(self.player).map(|mover| mover.tile);
+        if let Some((x, y)) = player_tile {
+            let in_bounds = x >= 0 && y >= 0 && x < self.level.size.x as i32 && y < self.level.size.y as i32;
+            if in_bounds && self.level.is_walkable(x, y) {
+                self.world.restore(state.world);
+                systems::sync_system(&mut self.world, &self.level);
+            }
+        }
+    }
+
+    /// Changes how many world units make up a tile, keeping the player on the same spot of the maze
+    pub fn set_tile_size(&mut self, tile_size: f32) {
+        let ratio = tile_size / self.level.get_tile_size();
+
+        self.level.set_tile_size(tile_size);
+        systems::rescale_system(&mut self.world, ratio, &self.level);
+    }
+}
+
+impl<'s> Drawable for Game<'s> {
+    fn draw<RT: RenderTarget>(&self, target: &mut RT) {
+        target.draw(&self.level);
+        target.draw(&self.world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support;
+
+    const CORRIDOR: [&'static str; 3] = ["#######",
+                                         "#P    #",
+                                         "#######"];
+
+    // Pac-Man heading right from (2, 1) and the second player's ghost heading left from (3, 1),
+    // both just short of the edge between their tiles
+    fn head_on<'s>(game: &mut Game<'s>) -> Entity {
+        game.ghost_count = 1;
+        game.set_party(Some(Party::VERSUS));
+        let tile_size = game.level.get_tile_size();
+        let ghost = game.world.ghosts.iter().map(|(entity, _)| entity).next().unwrap();
+        for &(entity, tile, ref direction, offset) in [(game.player, (2, 1), MoveDirection::RIGHT, 0.45),
+                                                        (ghost, (3, 1), MoveDirection::LEFT, -0.45)].iter() {
+            let mover = game.world.movers.get_mut(entity).unwrap();
+            mover.set_tile(tile);
+            mover.direction = direction.clone();
+            mover.offset = Vector2f::new(offset * tile_size, 0.0);
+        }
+        game.set_action(1, MoveDirection::LEFT);
+        return ghost;
+    }
+
+    #[test]
+    fn scripts_see_tiles_that_changed() {
+        use std::{env, fs};
+
+        // Puts a pellet back behind Pac-Man once it sees the first one has gone
+        let script_path = env::temp_dir().join("rustman-script-view-test.rhai").to_string_lossy().into_owned();
+        fs::write(&script_path, "fn on_tick(tick) { if tile(2, 1) == \"space\" { spawn_pickup(1, 1, \"pellet\"); } }").unwrap();
+
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["#######",
+                                              "#P.   #",
+                                              "#######"]);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        game.ghost_count = 0;
+        game.reset_world();
+        game.set_script(Script::load(&script_path).unwrap());
+
+        let mut game_time = GameTime::new();
+        for _ in 0..20 {
+            game_time.advance(STEP_MS);
+            game.update(MoveDirection::RIGHT, &game_time, &mut ());
+        }
+        assert!(game.level.get_tile(1, 1).level_type == LevelType::PELLET);
+    }
+
+    #[test]
+    fn fruit_comes_out_after_seventy_pellets() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let row = format!("#P{}#", ".".repeat(80));
+        let walls = "#".repeat(row.len());
+        let image = test_support::map_image(&[&walls, &row, &walls]);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        game.ghost_count = 0;
+        game.reset_world();
+
+        let mut game_time = GameTime::new();
+        let mut fruit = vec![];
+        while game.level.get_pellet_count() > 0 {
+            game_time.advance(STEP_MS);
+            game.update(MoveDirection::RIGHT, &game_time, &mut ());
+            if game.events.contains(&GameEvent::FruitSpawned { tile: (1, 1) }) {
+                fruit.push(game.initial_pellet_count - game.level.get_pellet_count());
+            }
+        }
+        assert_eq!(fruit, vec![70]);
+    }
+
+    #[test]
+    fn progress_starts_over_on_the_next_level() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["######",
+                                              "#P...#",
+                                              "######"]);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        game.level.set_tile(2, 1, LevelType::SPACE);
+        game.on_level_edited();
+
+        game.next_level();
+        assert_eq!(game.get_progress(), 0.0);
+    }
+
+    #[test]
+    fn movers_swapping_tiles_head_on_collide() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&CORRIDOR);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        head_on(&mut game);
+
+        let mut game_time = GameTime::new();
+        game_time.advance(STEP_MS);
+        game.update(MoveDirection::NONE, &game_time, &mut ());
+        assert!(game.events.contains(&GameEvent::PlayerDied));
+    }
+
+    #[test]
+    fn frightened_ghosts_swapping_tiles_are_eaten() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&CORRIDOR);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        let ghost = head_on(&mut game);
+        game.frightened_time = FRIGHTENED_MS;
+
+        let mut game_time = GameTime::new();
+        game_time.advance(STEP_MS);
+        game.update(MoveDirection::NONE, &game_time, &mut ());
+        assert!(!game.events.contains(&GameEvent::PlayerDied));
+        assert_eq!(game.world.movers.get(ghost).unwrap().tile, game.world.ghosts.get(ghost).unwrap().home);
+    }
+}
@@ -15,7 +15,15 @@ pub struct GameTime {
 
     pub ticks: units::MS,
     pub fixed_ticks: units::MS,
-    pub fps: i32,
+
+    /// Stops delta_time and the fixed update accumulator, the frame times keep measuring the real clock
+    pub paused: bool,
+    /// Multiplies delta_time, below 1 for slow motion
+    pub time_scale: f32,
+
+    /// Microsecond versions of the frame times, millisecond integers are too coarse above 60 FPS
+    pub start_frame_us: units::US,
+    pub elapsed_us: units::US,
 }
 
 impl GameTime {
@@ -34,13 +42,124 @@ impl GameTime {
 
             ticks: 0,
             fixed_ticks: 0,
-            fps: 0,
+
+            paused: false,
+            time_scale: 1.0,
+
+            start_frame_us: clock.get_elapsed_time().as_microseconds(),
+            elapsed_us: 0,
 
             clock: clock,
         }
     }
 
-    pub fn get_time_in_ms(&self) -> units::MS {
-        self.clock.get_elapsed_time().as_milliseconds() as units::MS
+    pub fn get_time_in_us(&self) -> units::US {
+        self.clock.get_elapsed_time().as_microseconds()
+    }
+
+    /// Measures the frame that just ended from the clock and adds it to the fixed update accumulator,
+    /// which never holds more than `max_fixed_time`
+    pub fn begin_frame(&mut self, max_fixed_time: units::DT) {
+        let now = self.get_time_in_us();
+        self.elapsed_us = now - self.start_frame_us;
+        self.start_frame_us = now;
+
+        self.previous_frame_time = self.start_frame_time;
+        self.start_frame_time = (now / 1000) as units::MS;
+        self.elapsed_time = self.start_frame_time - self.previous_frame_time;
+
+        // Beyond what the game loop can catch up on, a long stall like a window drag or a debugger pause is dropped
+        let frame_ms = self.elapsed_us as units::DT / 1000.0;
+        self.delta_time = if self.paused { 0.0 } else { frame_ms * self.time_scale };
+        self.fixed_time = (self.fixed_time + self.delta_time).min(max_fixed_time);
+        self.ticks += 1;
     }
-}
\ No newline at end of file
+
+    /// Steps the clock forward by a fixed amount, for running the simulation without a window
+    pub fn advance(&mut self, delta_time: units::DT) {
+        self.elapsed_us = (delta_time * 1000.0) as units::US;
+        self.start_frame_us += self.elapsed_us;
+
+        self.previous_frame_time = self.start_frame_time;
+        self.start_frame_time = (self.start_frame_us / 1000) as units::MS;
+        self.elapsed_time = self.start_frame_time - self.previous_frame_time;
+
+        self.delta_time = delta_time;
+        self.ticks += 1;
+    }
+}
+
+/// Time that only passes while the game is running, for anything that shouldn't jump forward after a pause
+pub struct Timer {
+    elapsed: units::DT
+}
+
+impl Timer {
+    pub fn new() -> Timer {
+        Timer {
+            elapsed: 0.0
+        }
+    }
+
+    /// Adds the frame's delta_time, which is already scaled and is zero while paused
+    pub fn update(&mut self, game_time: &GameTime) {
+        self.elapsed += game_time.delta_time;
+    }
+
+    pub fn get_elapsed(&self) -> units::DT {
+        self.elapsed
+    }
+
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_time_is_capped() {
+        let mut game_time = GameTime::new();
+        game_time.fixed_time = 1000.0;
+        game_time.begin_frame(250.0);
+        assert_eq!(game_time.fixed_time, 250.0);
+    }
+
+    // Backdates the frame's start so the next begin_frame measures at least `ms` of real time
+    fn frame_of(game_time: &mut GameTime, ms: units::US) {
+        game_time.start_frame_us = game_time.get_time_in_us() - ms * 1000;
+        game_time.begin_frame(1000.0);
+    }
+
+    #[test]
+    fn nothing_passes_while_paused() {
+        let mut game_time = GameTime::new();
+        let mut timer = Timer::new();
+        game_time.fixed_time = 5.0;
+        game_time.paused = true;
+
+        frame_of(&mut game_time, 10);
+        timer.update(&game_time);
+        assert!(game_time.elapsed_us >= 10_000);
+        assert_eq!(game_time.delta_time, 0.0);
+        assert_eq!(game_time.fixed_time, 5.0);
+        assert_eq!(timer.get_elapsed(), 0.0);
+    }
+
+    #[test]
+    fn time_scale_slows_the_game_but_not_the_frame_times() {
+        let mut game_time = GameTime::new();
+        let mut timer = Timer::new();
+        game_time.time_scale = 0.5;
+
+        frame_of(&mut game_time, 10);
+        timer.update(&game_time);
+        let frame_ms = game_time.elapsed_us as units::DT / 1000.0;
+        assert!(frame_ms >= 10.0);
+        assert_eq!(game_time.delta_time, frame_ms * 0.5);
+        assert_eq!(game_time.fixed_time, frame_ms * 0.5);
+        assert_eq!(timer.get_elapsed(), frame_ms * 0.5);
+    }
+}
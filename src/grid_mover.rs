@@ -0,0 +1,315 @@
+use sfml::system::Vector2f;
+
+use level::Level;
+use player::MoveDirection;
+use units;
+
+/// Moves an actor along tile centres, like the arcade. The position is a tile plus an offset from its centre,
+/// and the offset lies along the direction of travel except while cutting a corner.
+/// Turns happen at tile centres, or within the cornering windows either side of them, reversing at any time.
+#[derive(Clone)]
+pub struct GridMover {
+    pub tile: (i32, i32),
+    /// World units from the centre of `tile`, never more than half a tile
+    pub offset: Vector2f,
+    pub direction: MoveDirection,
+    /// Direction asked for that couldn't be taken yet, it is taken at the next tile centre where it's open
+    pub desired_direction: MoveDirection,
+    /// Tiles per millisecond
+    pub speed: f32,
+    /// The wall the mover is stopped against, if any
+    pub blocked_tile: Option<(i32, i32)>,
+    /// Tiles before a tile centre that a turn may be taken early, cutting the corner
+    pub pre_turn: f32,
+    /// Tiles past a tile centre that a turn may still be taken late
+    pub post_turn: f32
+}
+
+impl GridMover {
+    pub fn new(tile: (i32, i32), speed: f32) -> GridMover {
+        GridMover {
+            tile: tile,
+            offset: Vector2f::new(0.0, 0.0),
+            direction: MoveDirection::NONE,
+            desired_direction: MoveDirection::NONE,
+            speed: speed,
+            blocked_tile: None,
+            pre_turn: 0.0,
+            post_turn: 0.0
+        }
+    }
+
+    /// Lets the mover turn part of a tile either side of a tile centre, as Pac-Man can but the ghosts can't
+    pub fn set_cornering(&mut self, pre_turn: f32, post_turn: f32) {
+        self.pre_turn = pre_turn;
+        self.post_turn = post_turn;
+    }
+
+    /// Puts the mover on the centre of a tile
+    pub fn set_tile(&mut self, tile: (i32, i32)) {
+        self.tile = tile;
+        self.offset = Vector2f::new(0.0, 0.0);
+        self.blocked_tile = None;
+    }
+
+    pub fn get_world_position(&self, level: &Level) -> Vector2f {
+        level.tile_centre_to_world(self.tile.0, self.tile.1) + self.offset
+    }
+
+    pub fn is_at_centre(&self) -> bool {
+        self.offset.x == 0.0 && self.offset.y == 0.0
+    }
+
+    /// Asks to go a new way, NONE keeps the current request
+    pub fn request_direction(&mut self, direction: MoveDirection) {
+        if direction != MoveDirection::NONE {
+            self.desired_direction = direction;
+        }
+    }
+
+    pub fn update(&mut self, delta_time: units::DT, level: &Level) {
+        let tile_size = level.get_tile_size();
+        self.try_turn(level, tile_size);
+
+        let mut remaining = self.speed * tile_size * delta_time;
+        while remaining > 0.0 && self.direction != MoveDirection::NONE {
+            if self.is_at_centre() {
+                self.try_turn(level, tile_size);
+                let ahead = get_neighbour(self.tile, &self.direction);
+                if !level.is_walkable(ahead.0, ahead.1) {
+                    self.blocked_tile = Some(ahead);
+                    return;
+                }
+            }
+            self.blocked_tile = None;
+
+            // Never move past the next tile centre in one go, so every centre gets a chance to turn or stop.
+            // This sweeps the whole path, however long the step, so a wall can't be skipped over.
+            let unit = get_unit_vector(&self.direction);
+            let along = self.offset.x * unit.x + self.offset.y * unit.y;
+            let to_next_centre = if along < 0.0 { -along } else { tile_size - along };
+
+            if remaining >= to_next_centre {
+                remaining -= to_next_centre;
+                if along >= 0.0 {
+                    self.tile = get_neighbour(self.tile, &self.direction);
+                }
+                self.offset = Vector2f::new(0.0, 0.0);
+            } else {
+                // After cutting a corner the old axis is closed at the same rate, so the mover travels diagonally
+                let across = self.offset - unit * along;
+                let across_length = across.x.abs() + across.y.abs();
+                let across_left = if across_length > remaining { across * ((across_length - remaining) / across_length) }
+                                  else { Vector2f::new(0.0, 0.0) };
+
+                self.offset = unit * (along + remaining) + across_left;
+                remaining = 0.0;
+                if self.offset.x * unit.x + self.offset.y * unit.y >= tile_size / 2.0 {
+                    self.tile = get_neighbour(self.tile, &self.direction);
+                    self.offset = self.offset - unit * tile_size;
+                }
+            }
+        }
+    }
+
+    // Takes the desired direction if it's allowed from where the mover is now
+    fn try_turn(&mut self, level: &Level, tile_size: f32) {
+        let desired = self.desired_direction.clone();
+        if desired == MoveDirection::NONE || desired == self.direction {
+            return;
+        }
+
+        let reversing = desired == get_opposite(&self.direction);
+        let next = get_neighbour(self.tile, &desired);
+
+        // Negative before the centre, positive past it
+        let unit = get_unit_vector(&self.direction);
+        let along = self.offset.x * unit.x + self.offset.y * unit.y;
+        let in_window = if along < 0.0 { -along <= self.pre_turn * tile_size }
+                        else { along <= self.post_turn * tile_size };
+
+        if reversing || self.direction == MoveDirection::NONE && self.is_at_centre() {
+            self.direction = desired;
+        } else if in_window && level.is_walkable(next.0, next.1) {
+            // The offset is kept and closed off while moving the new way
+            self.direction = desired;
+        } else {
+            return;
+        }
+        self.desired_direction = MoveDirection::NONE;
+    }
+}
+
+pub fn get_neighbour(tile: (i32, i32), direction: &MoveDirection) -> (i32, i32) {
+    match *direction {
+        MoveDirection::UP => (tile.0, tile.1 - 1),
+        MoveDirection::DOWN => (tile.0, tile.1 + 1),
+        MoveDirection::LEFT => (tile.0 - 1, tile.1),
+        MoveDirection::RIGHT => (tile.0 + 1, tile.1),
+        MoveDirection::NONE => tile
+    }
+}
+
+pub fn get_unit_vector(direction: &MoveDirection) -> Vector2f {
+    match *direction {
+        MoveDirection::UP => Vector2f::new(0.0, -1.0),
+        MoveDirection::DOWN => Vector2f::new(0.0, 1.0),
+        MoveDirection::LEFT => Vector2f::new(-1.0, 0.0),
+        MoveDirection::RIGHT => Vector2f::new(1.0, 0.0),
+        MoveDirection::NONE => Vector2f::new(0.0, 0.0)
+    }
+}
+
+pub fn get_opposite(direction: &MoveDirection) -> MoveDirection {
+    match *direction {
+        MoveDirection::UP => MoveDirection::DOWN,
+        MoveDirection::DOWN => MoveDirection::UP,
+        MoveDirection::LEFT => MoveDirection::RIGHT,
+        MoveDirection::RIGHT => MoveDirection::LEFT,
+        MoveDirection::NONE => MoveDirection::NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use player;
+    use test_support;
+
+    // The junction at (2, 2) opens up, left and right, tiles are the default 8 world units
+    const JUNCTION: [&'static str; 4] = ["#####",
+                                         "## ##",
+                                         "#   #",
+                                         "#####"];
+
+    fn moving_right_at(offset_x: f32) -> GridMover {
+        let mut mover = GridMover::new((2, 2), 1.0 / 8.0);
+        mover.set_cornering(player::PRE_TURN, player::POST_TURN);
+        mover.direction = MoveDirection::RIGHT;
+        mover.offset = Vector2f::new(offset_x, 0.0);
+        return mover;
+    }
+
+    #[test]
+    fn turns_inside_the_pre_turn_window() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&JUNCTION);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        let mut mover = moving_right_at(-4.0);
+        mover.request_direction(MoveDirection::UP);
+        mover.update(0.0, &level);
+        assert_eq!(mover.direction, MoveDirection::UP);
+        assert_eq!(mover.desired_direction, MoveDirection::NONE);
+    }
+
+    #[test]
+    fn turns_inside_the_post_turn_window() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&JUNCTION);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        let mut mover = moving_right_at(3.0);
+        mover.request_direction(MoveDirection::UP);
+        mover.update(0.0, &level);
+        assert_eq!(mover.direction, MoveDirection::UP);
+    }
+
+    #[test]
+    fn keeps_going_outside_both_windows() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&JUNCTION);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        for &offset_x in [-4.5, 3.5].iter() {
+            let mut mover = moving_right_at(offset_x);
+            mover.request_direction(MoveDirection::UP);
+            mover.update(0.0, &level);
+            assert_eq!(mover.direction, MoveDirection::RIGHT);
+            // Still wanted, for the next centre that allows it
+            assert_eq!(mover.desired_direction, MoveDirection::UP);
+        }
+    }
+
+    #[test]
+    fn cutting_a_corner_closes_the_offset_diagonally() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&JUNCTION);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        let mut mover = moving_right_at(-3.0);
+        mover.request_direction(MoveDirection::UP);
+
+        // One world unit a millisecond: two up, and two of the three left over from the old axis closed
+        mover.update(2.0, &level);
+        assert_eq!(mover.tile, (2, 2));
+        assert_eq!(mover.offset, Vector2f::new(-1.0, -2.0));
+
+        // The old axis is closed by the time the mover crosses into the tile above
+        mover.update(3.0, &level);
+        assert_eq!(mover.tile, (2, 1));
+        assert_eq!(mover.offset, Vector2f::new(0.0, 3.0));
+    }
+
+    // Walls at either end and one in the middle, (1, 1) to (3, 1) and (5, 1) to (8, 1) are open
+    const SPLIT_CORRIDOR: [&'static str; 3] = ["##########",
+                                               "#   #    #",
+                                               "##########"];
+
+    #[test]
+    fn a_step_of_several_tiles_stops_at_the_wall() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&SPLIT_CORRIDOR);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        // Ten tiles in one update, from one tile short of the middle wall
+        let mut mover = GridMover::new((2, 1), 1.0 / 8.0);
+        mover.request_direction(MoveDirection::RIGHT);
+        mover.update(80.0, &level);
+        assert_eq!(mover.tile, (3, 1));
+        assert!(mover.is_at_centre());
+        assert_eq!(mover.blocked_tile, Some((4, 1)));
+    }
+
+    #[test]
+    fn a_step_of_several_tiles_runs_the_whole_corridor() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&SPLIT_CORRIDOR);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        // Two and a half tiles, ending between centres
+        let mut mover = GridMover::new((5, 1), 1.0 / 8.0);
+        mover.request_direction(MoveDirection::RIGHT);
+        mover.update(20.0, &level);
+        assert_eq!(mover.tile, (8, 1));
+        assert_eq!(mover.offset, Vector2f::new(-4.0, 0.0));
+
+        // Then far more than is left, stopping on the last tile
+        mover.update(400.0, &level);
+        assert_eq!(mover.tile, (8, 1));
+        assert!(mover.is_at_centre());
+        assert_eq!(mover.blocked_tile, Some((9, 1)));
+    }
+
+    #[test]
+    fn a_step_of_several_tiles_turns_at_the_corner() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["#####",
+                                              "### #",
+                                              "### #",
+                                              "#   #",
+                                              "#####"]);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+
+        // Along to the corner at (3, 3), then up to the dead end at (3, 1)
+        let mut mover = GridMover::new((1, 3), 1.0 / 8.0);
+        mover.request_direction(MoveDirection::RIGHT);
+        mover.update(0.0, &level);
+        mover.request_direction(MoveDirection::UP);
+        mover.update(80.0, &level);
+        assert_eq!(mover.direction, MoveDirection::UP);
+        assert_eq!(mover.tile, (3, 1));
+        assert!(mover.is_at_centre());
+        assert_eq!(mover.blocked_tile, Some((3, 0)));
+    }
+}
@@ -0,0 +1,74 @@
+use bot::Bot;
+use events::GameEvent;
+use game::{self, Game};
+use game_time::GameTime;
+use replay::{Replay, ReplayRecorder};
+use error::GameError;
+
+// Exit codes for runs that didn't end the way they should
+const EXIT_REPLAY_DESYNC: i32 = 1;
+const EXIT_OUT_OF_FRAMES: i32 = 2;
+
+/// Runs games without a window, played by a replay if there is one or else the bot, returns the exit code.
+/// `new_game` builds each game from its index. A game ends once the ghosts have taken its last life, the replay reaches its end
+/// or after `frames` fixed updates, clearing a level moves on to the next like the windowed game.
+/// Exits with 1 if a replay finished on a different score to the recording, or 2 if `frames` ran out before it ended.
+pub fn run<'s, F>(mut new_game: F, mut replay: Option<Replay>, mut recorder: Option<ReplayRecorder>, frames: u32, games: u32) -> Result<i32, GameError>
+    where F: FnMut(u32) -> Result<Game<'s>, GameError> {
+
+    let mut total_points: u64 = 0;
+    let mut levels_cleared = 0;
+    let mut exit_code = 0;
+
+    for game_index in 0..games {
+        let mut game = new_game(game_index)?;
+        let mut game_time = GameTime::new();
+        let mut bot = Bot::new();
+        let mut tick = 0;
+
+        // A replay runs to its recorded end, which is where the recording's game ended too
+        while tick < frames && (replay.is_some() || game.score.lives > 0) {
+            if replay.as_ref().map(|replay| replay.is_finished(tick + 1)).unwrap_or(false) {
+                break;
+            }
+            tick += 1;
+
+            game_time.advance(game::STEP_MS);
+            let action = match replay {
+                Some(ref mut replay) => replay.get_action(tick),
+                None => bot.get_action(game.get_player_mover(), &game.level, &game.get_threats())
+            };
+            if let Some(ref mut recorder) = recorder {
+                recorder.record(tick, &action);
+            }
+            game.update(action, &game_time, &mut ());
+
+            if game.events.contains(&GameEvent::LevelCleared) {
+                game.next_level();
+                levels_cleared += 1;
+            }
+        }
+
+        println!("Game {} finished after {} updates: score {}, lives {}, level {}, {} pellets left",
+                 game_index + 1, tick, game.score.points, game.score.lives, game.level_number, game.level.get_pellet_count());
+        total_points += game.score.points as u64;
+
+        if let Some((end_tick, end_points)) = replay.as_ref().and_then(|replay| replay.end) {
+            if tick < end_tick {
+                eprintln!("Ran out of frames at update {}, the replay ends at {}", tick, end_tick);
+                exit_code = EXIT_OUT_OF_FRAMES;
+            } else if game.score.points != end_points {
+                eprintln!("The replay finished on score {} but was recorded finishing on {}", game.score.points, end_points);
+                exit_code = EXIT_REPLAY_DESYNC;
+            }
+        }
+        if let Some(recorder) = recorder.take() {
+            recorder.finish(tick, game.score.points)?;
+        }
+    }
+
+    if games > 1 {
+        println!("{} games: average score {}, {} levels cleared", games, total_points / games as u64, levels_cleared);
+    }
+    return Ok(exit_code);
+}
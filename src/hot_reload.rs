@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use resources::Assets;
+use units;
+
+// Checking every frame would hit the disk far more than a designer can save
+const POLL_INTERVAL_MS: units::DT = 500.0;
+
+struct WatchedFile {
+    resource_path: String,
+    file_path: PathBuf,
+    modified: Option<SystemTime>
+}
+
+/// Polls the modification times of loose resource files, for reloading them while the game runs.
+/// Resources that only exist inside the archive can't change and aren't watched.
+pub struct FileWatcher {
+    files: Vec<WatchedFile>,
+    time_since_poll: units::DT
+}
+
+impl FileWatcher {
+    pub fn new(assets: &Assets, resource_paths: &[&str]) -> FileWatcher {
+        let mut files = vec![];
+        for resource_path in resource_paths.iter() {
+            match assets.resolve_file(resource_path) {
+                Ok(file_path) => {
+                    eprintln!("Watching {} for changes", file_path.display());
+                    files.push(WatchedFile {
+                        resource_path: resource_path.to_string(),
+                        modified: FileWatcher::get_modified(&file_path),
+                        file_path: file_path
+                    });
+                },
+                Err(_) => eprintln!("{} is not a loose file, it won't be reloaded", resource_path)
+            }
+        }
+
+        FileWatcher {
+            files: files,
+            time_since_poll: 0.0
+        }
+    }
+
+    /// Returns the resource paths of every file that changed since the last poll
+    pub fn poll(&mut self, delta_time: units::DT) -> Vec<String> {
+        self.time_since_poll += delta_time;
+        if self.time_since_poll < POLL_INTERVAL_MS {
+            return vec![];
+        }
+        self.time_since_poll = 0.0;
+
+        let mut changed = vec![];
+        for file in self.files.iter_mut() {
+            let modified = FileWatcher::get_modified(&file.file_path);
+            // A file that is missing part way through being saved is picked up on a later poll
+            if modified.is_some() && modified != file.modified {
+                file.modified = modified;
+                changed.push(file.resource_path.clone());
+            }
+        }
+        return changed;
+    }
+
+    fn get_modified(file_path: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(file_path).and_then(|metadata| metadata.modified()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::UNIX_EPOCH;
+
+    const MAP: &'static str = "res/game_map.png";
+
+    // Pretends the map was last seen long ago, as if it has since been saved
+    fn watcher_with_stale_map() -> FileWatcher {
+        let mut watcher = FileWatcher::new(&Assets::new_loose(), &[MAP, "res/missing.png"]);
+        watcher.files[0].modified = Some(UNIX_EPOCH);
+        return watcher;
+    }
+
+    #[test]
+    fn only_loose_files_are_watched() {
+        let watcher = FileWatcher::new(&Assets::new_loose(), &[MAP, "res/missing.png"]);
+        assert_eq!(watcher.files.len(), 1);
+        assert_eq!(watcher.files[0].resource_path, MAP);
+    }
+
+    #[test]
+    fn changes_are_reported_once_the_poll_interval_passes() {
+        let mut watcher = watcher_with_stale_map();
+        assert!(watcher.poll(POLL_INTERVAL_MS / 2.0).is_empty());
+        assert_eq!(watcher.poll(POLL_INTERVAL_MS / 2.0), vec![MAP.to_string()]);
+    }
+
+    #[test]
+    fn unchanged_files_are_not_reported_again() {
+        let mut watcher = watcher_with_stale_map();
+        assert_eq!(watcher.poll(POLL_INTERVAL_MS), vec![MAP.to_string()]);
+        assert!(watcher.poll(POLL_INTERVAL_MS).is_empty());
+    }
+}
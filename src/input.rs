@@ -1,9 +1,12 @@
 use sfml::window::event;
 use sfml::window::event::Event;
 use sfml::window::keyboard::Key;
+use sfml::window::joystick::{self, Axis};
 use std::collections::HashMap;
 use std::hash::{Hash, Hasher};
 
+use player::MoveDirection;
+
 #[derive(Eq, PartialEq)]
 struct HKey(Key);
 
@@ -13,6 +16,27 @@ impl Hash for HKey {
     }
 }
 
+/// Keys that steer one player
+pub struct Bindings {
+    pub up: Key,
+    pub down: Key,
+    pub left: Key,
+    pub right: Key
+}
+
+pub const WASD: Bindings = Bindings { up: Key::W, down: Key::S, left: Key::A, right: Key::D };
+pub const ARROWS: Bindings = Bindings { up: Key::Up, down: Key::Down, left: Key::Left, right: Key::Right };
+
+// How far a gamepad stick has to be pushed, out of 100, to count as a direction
+const STICK_DEAD_ZONE: f32 = 50.0;
+
+/// Where a player's directions come from
+pub enum Device {
+    KEYS(&'static Bindings),
+    /// A joystick or gamepad by its index
+    GAMEPAD(u32)
+}
+
 pub struct Input {
     held_keys: HashMap<HKey, bool>,
     keys_down: HashMap<HKey, bool>,
@@ -56,6 +80,42 @@ impl Input {
         (*self.keys_up.get(&HKey(key)).unwrap_or(&false) == true)
     }
 
+    /// Direction requested with the bindings this frame, NONE if none of their keys was pressed
+    pub fn get_move_direction(&self, bindings: &Bindings) -> MoveDirection {
+        if self.is_key_down(bindings.right) {
+            MoveDirection::RIGHT
+        } else if self.is_key_down(bindings.left) {
+            MoveDirection::LEFT
+        } else if self.is_key_down(bindings.down) {
+            MoveDirection::DOWN
+        } else if self.is_key_down(bindings.up) {
+            MoveDirection::UP
+        } else {
+            MoveDirection::NONE
+        }
+    }
+
+    /// Direction requested by a player's device. Sticks are read as held, which is fine as NONE keeps going.
+    pub fn get_device_direction(&self, device: &Device) -> MoveDirection {
+        match *device {
+            Device::KEYS(bindings) => self.get_move_direction(bindings),
+            Device::GAMEPAD(index) => {
+                if !joystick::is_connected(index) {
+                    return MoveDirection::NONE;
+                }
+                let x = joystick::get_axis_position(index, Axis::X);
+                let y = joystick::get_axis_position(index, Axis::Y);
+                if x.abs() < STICK_DEAD_ZONE && y.abs() < STICK_DEAD_ZONE {
+                    MoveDirection::NONE
+                } else if x.abs() > y.abs() {
+                    if x > 0.0 { MoveDirection::RIGHT } else { MoveDirection::LEFT }
+                } else {
+                    if y > 0.0 { MoveDirection::DOWN } else { MoveDirection::UP }
+                }
+            }
+        }
+    }
+
     fn key_pressed(&mut self, key: Key) {
         self.keys_down.insert(HKey(key), true);
         self.held_keys.insert(HKey(key), true);
@@ -1,51 +1,71 @@
 use std::cmp;
 
 use sfml::system::{Vector2f, Vector2u};
-use sfml::graphics::{Color, RenderStates, RenderTarget, VertexArray, PrimitiveType, Image, Texture};
+use sfml::graphics::{RenderStates, RenderTarget, VertexArray, PrimitiveType, Image, Sprite, FloatRect};
 use sfml::traits::Drawable;
 
 use level_object::{LevelObject, LevelType};
 use sprite_sheet::SpriteSheet;
+use map_format;
+
+// World units are arcade pixels, 8 to a tile. The screen scale is only applied by the view.
+const DEFAULT_TILE_SIZE: f32 = 8.0;
 
 pub struct Level<'s> {
     pub size: Vector2u,
+    tile_size: f32,
     map: Vec<Vec<LevelObject<'s>>>,
     vertex_array: VertexArray,
-    texture: &'s Texture
+    sprite_sheet: &'s SpriteSheet,
+    player_spawn: Option<(i32, i32)>,
+    /// Tiles set since `take_changed_tiles` was last called
+    changed_tiles: Vec<(i32, i32)>
 }
 
 impl<'s> Level<'s> {
  
     pub fn new_with_map(size: Vector2u, map: Vec<Vec<LevelType>>, sprite_sheet: &'s SpriteSheet) -> Level<'s> {
-        let new_map = Level::setup_map(&map, sprite_sheet);
+        let new_map = Level::setup_map(&map, sprite_sheet, DEFAULT_TILE_SIZE);
 
-        Level {
+        let mut level = Level {
             size: size,
+            tile_size: DEFAULT_TILE_SIZE,
             map: new_map,
             vertex_array: VertexArray::new_init(PrimitiveType::Quads, (size.x * size.y * 4)).unwrap(),
-            texture: &sprite_sheet.texture
+            sprite_sheet: sprite_sheet,
+            player_spawn: None,
+            changed_tiles: vec![]
+        };
+        for x in 0..size.x as i32 {
+            for y in 0..size.y as i32 {
+                level.update_quad(x, y);
+            }
         }
+        return level;
     }
     
     pub fn new_with_image(image: &Image, sprite_sheet: &'s SpriteSheet) -> Level<'s> {
         let level_size = image.get_size();
         let mut map = vec![];
+        let mut player_spawn = None;
         
         for x in 0..level_size.x + 1 {
             map.push(vec![]);
             for y in 0..level_size.y + 1 {
-                let level_type = match image.get_pixel(x, y) {
-                    Color { red: 0, green: 0, blue: 255, alpha: 255 } => { LevelType::WALL },
-                    _ => { LevelType::SPACE }
-                };
+                let (level_type, spawn) = map_format::color_to_level_type(&image.get_pixel(x, y));
+                if spawn && x < level_size.x && y < level_size.y {
+                    player_spawn = Some((x as i32, y as i32));
+                }
                 map.last_mut().unwrap().push(level_type);
             }
         }
         
-        Level::new_with_map(level_size, map, sprite_sheet)
+        let mut level = Level::new_with_map(level_size, map, sprite_sheet);
+        level.player_spawn = player_spawn;
+        return level;
     }
     
-    pub fn setup_map(map: &Vec<Vec<LevelType>>, sprite_sheet: &'s SpriteSheet) -> Vec<Vec<LevelObject<'s>>> {
+    pub fn setup_map(map: &Vec<Vec<LevelType>>, sprite_sheet: &'s SpriteSheet, tile_size: f32) -> Vec<Vec<LevelObject<'s>>> {
 
         let mut returned_map = vec![vec![]];
         
@@ -53,13 +73,7 @@ impl<'s> Level<'s> {
             returned_map.push(Vec::<LevelObject<'s>>::new());
             for y in 0..map[0].len() {
                 let level_type = map[x][y].clone();
-                let mut sprite = sprite_sheet.generate_background_sprite(&level_type);
-                
-                let local_bounds = sprite.get_local_bounds();
-                let new_scale = Vector2f::new(super::GAME_SIZE as f32 / local_bounds.width, super::GAME_SIZE as f32 / local_bounds.height);
-        
-                sprite.set_scale(&new_scale);
-                sprite.set_position(&Level::tile_to_world(x as i32, y as i32));
+                let sprite = Level::create_tile_sprite(&level_type, x as i32, y as i32, sprite_sheet, tile_size);
                 
                 let level_object = LevelObject::new(level_type, sprite);
                 returned_map[x].push(level_object);
@@ -68,69 +82,136 @@ impl<'s> Level<'s> {
         return returned_map;
     }
     
+    fn create_tile_sprite(level_type: &LevelType, x: i32, y: i32, sprite_sheet: &'s SpriteSheet, tile_size: f32) -> Sprite<'s> {
+        let mut sprite = sprite_sheet.generate_background_sprite(level_type);
+        
+        // Pellets only take up part of the tile and sit in the centre of it
+        let sprite_size = match *level_type {
+            LevelType::PELLET => tile_size / 4.0,
+            LevelType::POWERPELLET => tile_size / 2.0,
+            _ => tile_size
+        };
+        let offset = (tile_size - sprite_size) / 2.0;
+        
+        let local_bounds = sprite.get_local_bounds();
+        let new_scale = Vector2f::new(sprite_size / local_bounds.width, sprite_size / local_bounds.height);
+        
+        sprite.set_scale(&new_scale);
+        sprite.set_position2f(x as f32 * tile_size + offset, y as f32 * tile_size + offset);
+        return sprite;
+    }
+    
     pub fn get_tile(&self, x: i32, y: i32) -> &LevelObject {
        return &self.map[cmp::max(cmp::min(x, (self.size.x - 1) as i32), 0) as usize][cmp::max(cmp::min(y, (self.size.y - 1) as i32), 0) as usize];
     }
     
-    pub fn world_to_tile(x: f32, y: f32) -> (i32, i32) {
-        let mut x = x as i32;
-        let mut y = y as i32;
-        
-        x &= !(super::GAME_SIZE as i32 - 1);
-        y &= !(super::GAME_SIZE as i32 - 1);
-        if x != 0 {
-            x /= super::GAME_SIZE as i32;
+    pub fn set_tile(&mut self, x: i32, y: i32, level_type: LevelType) {
+        if x < 0 || y < 0 || x >= self.size.x as i32 || y >= self.size.y as i32 {
+            return;
         }
-        if y != 0 {
-            y /= super::GAME_SIZE as i32;
+        let sprite = Level::create_tile_sprite(&level_type, x, y, self.sprite_sheet, self.tile_size);
+        self.map[x as usize][y as usize] = LevelObject::new(level_type, sprite);
+        self.update_quad(x, y);
+        self.changed_tiles.push((x, y));
+    }
+
+    /// Every tile set since the last call, for copies of the maze that only want to refresh what changed
+    pub fn take_changed_tiles(&mut self) -> Vec<(i32, i32)> {
+        self.changed_tiles.drain(..).collect()
+    }
+
+    // Copies a tile's sprite into its four vertices, the only place the vertex array is written
+    fn update_quad(&mut self, x: i32, y: i32) {
+        let level_object = &self.map[x as usize][y as usize];
+        let bounds = level_object.sprite.get_global_bounds();
+        let texture_rect = level_object.sprite.get_texture_rect();
+        let first_vertex = (x as u32 * self.size.y + y as u32) * 4;
+
+        let corners = [(bounds.left, bounds.top + bounds.height, texture_rect.left, texture_rect.top + texture_rect.height),
+                       (bounds.left, bounds.top, texture_rect.left, texture_rect.top),
+                       (bounds.left + bounds.width, bounds.top, texture_rect.left + texture_rect.width, texture_rect.top),
+                       (bounds.left + bounds.width, bounds.top + bounds.height,
+                        texture_rect.left + texture_rect.width, texture_rect.top + texture_rect.height)];
+        // Bottom left, top left, top right, bottom right
+        for (index, &(position_x, position_y, texture_x, texture_y)) in corners.iter().enumerate() {
+            let vertex = self.vertex_array.get_vertex(first_vertex + index as u32);
+            vertex.position = Vector2f::new(position_x, position_y);
+            vertex.tex_coords = Vector2f::new(texture_x as f32, texture_y as f32);
+        }
+    }
+    
+    /// Tile the player starts on, if the map marks one
+    pub fn get_player_spawn(&self) -> Option<(i32, i32)> {
+        self.player_spawn
+    }
+    
+    pub fn set_player_spawn(&mut self, player_spawn: Option<(i32, i32)>) {
+        self.player_spawn = player_spawn;
+    }
+    
+    pub fn get_tile_size(&self) -> f32 {
+        self.tile_size
+    }
+    
+    /// Rebuilds every tile at the new size. Actors need rescaling separately, see `Game::set_tile_size`.
+    pub fn set_tile_size(&mut self, tile_size: f32) {
+        self.tile_size = tile_size;
+        for x in 0..self.size.x as i32 {
+            for y in 0..self.size.y as i32 {
+                let level_type = self.get_tile(x, y).level_type.clone();
+                self.set_tile(x, y, level_type);
+            }
         }
-        return (x, y);
     }
     
-    pub fn tile_to_world(x: i32, y: i32) -> Vector2f {
-        Vector2f::new((x * super::GAME_SIZE as i32) as f32, (y * super::GAME_SIZE as i32) as f32)
+    pub fn get_world_rect(&self) -> FloatRect {
+        FloatRect::new(0.0, 0.0, self.size.x as f32 * self.tile_size, self.size.y as f32 * self.tile_size)
+    }
+    
+    /// Eats the pellet at the given tile, returning what kind it was
+    pub fn eat_pellet(&mut self, x: i32, y: i32) -> Option<LevelType> {
+        let level_type = self.get_tile(x, y).level_type.clone();
+        if level_type != LevelType::PELLET && level_type != LevelType::POWERPELLET {
+            return None;
+        }
+        self.set_tile(x, y, LevelType::SPACE);
+        return Some(level_type);
+    }
+    
+    pub fn get_pellet_count(&self) -> usize {
+        let mut count = 0;
+        for x in 0..self.size.x as usize {
+            for y in 0..self.size.y as usize {
+                let level_type = &self.map[x][y].level_type;
+                if *level_type == LevelType::PELLET || *level_type == LevelType::POWERPELLET {
+                    count += 1;
+                }
+            }
+        }
+        return count;
+    }
+    
+    pub fn is_walkable(&self, x: i32, y: i32) -> bool {
+        self.get_tile(x, y).level_type != LevelType::WALL
+    }
+    
+    pub fn world_to_tile(&self, x: f32, y: f32) -> (i32, i32) {
+        ((x / self.tile_size).floor() as i32, (y / self.tile_size).floor() as i32)
+    }
+    
+    pub fn tile_to_world(&self, x: i32, y: i32) -> Vector2f {
+        Vector2f::new(x as f32 * self.tile_size, y as f32 * self.tile_size)
+    }
+    
+    pub fn tile_centre_to_world(&self, x: i32, y: i32) -> Vector2f {
+        self.tile_to_world(x, y) + self.tile_size / 2.0
     }
 }
 
 impl<'s> Drawable for Level<'s> {
     fn draw<RT: RenderTarget>(&self, target: &mut RT) {
-        let mut vertex_count = 0;
-        
-        for x in 0..self.size.x {
-            for y in 0..self.size.y {
-                let level_object = &self.map[x as usize][y as usize];
-                
-                let level_object_bounds = level_object.sprite.get_global_bounds();
-                let texture_rect = level_object.sprite.get_texture_rect();
-                
-                // Bottom left
-                self.vertex_array.get_vertex(vertex_count + 0).position = Vector2f::new(level_object_bounds.left,
-                                                                                   level_object_bounds.top + level_object_bounds.height);
-                self.vertex_array.get_vertex(vertex_count + 0).tex_coords = Vector2f::new(texture_rect.left as f32,
-                                                                                   (texture_rect.top + texture_rect.height) as f32);
-                
-                // Top left
-                self.vertex_array.get_vertex(vertex_count + 1).position = Vector2f::new(level_object_bounds.left,
-                                                                                   level_object_bounds.top);
-                self.vertex_array.get_vertex(vertex_count + 1).tex_coords = Vector2f::new(texture_rect.left as f32,
-                                                                                     texture_rect.top as f32);
-                
-                // Top right
-                self.vertex_array.get_vertex(vertex_count + 2).position = Vector2f::new(level_object_bounds.left +
-                                                                                   level_object_bounds.width, level_object_bounds.top);
-                self.vertex_array.get_vertex(vertex_count + 2).tex_coords = Vector2f::new((texture_rect.left +
-                                                                                     texture_rect.width) as f32, texture_rect.top as f32);
-                
-                // Bottom right
-                self.vertex_array.get_vertex(vertex_count + 3).position = Vector2f::new(level_object_bounds.left +
-                                                                                   level_object_bounds.width, level_object_bounds.top + level_object_bounds.height);
-                self.vertex_array.get_vertex(vertex_count + 3).tex_coords = Vector2f::new((texture_rect.left +
-                                                                                    texture_rect.width) as f32, (texture_rect.top + texture_rect.height) as f32);
-                vertex_count += 4;
-            }
-        }
         let mut states = RenderStates::default();
-        states.texture = Some(self.texture);
+        states.texture = Some(&*self.sprite_sheet.texture);
         target.draw_with_renderstates(&self.vertex_array, &mut states);
     }
-}
\ No newline at end of file
+}
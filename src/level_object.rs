@@ -3,7 +3,9 @@ use sfml::graphics::{Sprite};
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum LevelType {
     SPACE,
-    WALL
+    WALL,
+    PELLET,
+    POWERPELLET
 }
 
 pub struct LevelObject<'s> {
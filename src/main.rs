@@ -1,129 +1,754 @@
 extern crate sfml;
+extern crate rhai;
 
 mod level;
 mod player;
+mod grid_mover;
+mod ecs;
+mod actor;
+mod systems;
+mod save_state;
+mod two_player;
+mod party;
+mod sprite_quad;
 mod input;
 mod game_time;
 mod units;
 mod level_object;
 mod sprite_sheet;
 mod animation_sprite;
+mod bot;
+mod rng;
+mod game;
+mod env;
+mod screenshot;
+mod display;
+mod audio;
+mod events;
+mod score;
+mod error;
+mod resources;
+mod hot_reload;
+mod options;
+mod validate;
+mod replay;
+mod headless;
+mod map_format;
+mod editor;
+mod debug_overlay;
+mod frame_stats;
+mod net;
+mod script;
+#[cfg(test)]
+mod test_support;
 
-use sfml::window::{ContextSettings, VideoMode, event, Close};
+use std::rc::Rc;
+use std::path::PathBuf;
+
+use sfml::window::event;
 use sfml::window::keyboard::Key;
-use sfml::graphics::{Image, RenderWindow, RenderTarget, Color, Text, Font};
+use sfml::window::mouse::MouseButton;
+use sfml::system::Vector2f;
+use sfml::graphics::{Image, RenderWindow, RenderTarget, Color, Text, VertexArray, PrimitiveType};
 
-use player::{Player};
-use input::Input;
+use input::{Input, Device};
 use game_time::GameTime;
+use sprite_sheet::SpriteSheet;
+use bot::Bot;
+use game::Game;
+use env::Env;
+use display::{Display, Layered};
+use audio::{Audio, SoundBank};
+use events::GameEvent;
+use player::MoveDirection;
+use error::GameError;
+use resources::{Assets, AssetFont, Archive};
+use hot_reload::FileWatcher;
+use options::Options;
+use replay::{Replay, ReplayRecorder, ReplayStart};
 use level::Level;
-use sprite_sheet::{SpriteType, SpriteSheet};
-use animation_sprite::{AnimationSprite};
+use editor::Editor;
+use debug_overlay::DebugOverlay;
+use frame_stats::FrameStats;
+use save_state::SaveState;
+use two_player::TwoPlayer;
+use net::{NetSession, Role};
+use script::Script;
 
 const TARGET_FPS: i32 = 60;
-const MS_PER_UPDATE: units::MS = 1000;
-//const MS_PER_FRAME: units::MS = MS_PER_UPDATE / TARGET_FPS;
+const MS_PER_UPDATE: units::DT = game::STEP_MS;
+// Most fixed updates run before drawing a frame, any more time than that is dropped after a long stall
+const MAX_UPDATES_PER_FRAME: u32 = 15;
+// Slowest and fastest the [ and ] keys can scale time to
+const MIN_TIME_SCALE: f32 = 0.125;
+const MAX_TIME_SCALE: f32 = 4.0;
+// Smallest and largest tile size, in world units, that - and = can set in dev mode
+const MIN_TILE_SIZE: f32 = 2.0;
+const MAX_TILE_SIZE: f32 = 64.0;
+// Height of the frame time graph's bars
+const FRAME_GRAPH_PIXELS_PER_MS: f32 = 4.0;
 
+// Pixels per tile when rendering at the arcade's native resolution
+const NATIVE_TILE_SIZE: u32 = 8;
+// Default window scale on top of the native resolution
 const SCREEN_SCALE: u32 = 4;
-const GAME_SIZE: u32 = 8 * SCREEN_SCALE;
 
 const MAP_PATH: &'static str = "res/game_map.png";
+// Keys that pick each of the editor's brushes
+const EDITOR_BRUSH_KEYS: [Key; 5] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5];
+const FONT_PATH: &'static str = "res/fonts/arial.ttf";
+// Save state used by F5 and F9 unless --load-state names another
+const QUICK_SAVE_PATH: &'static str = "quicksave.txt";
 
 
-fn main() {
+fn main() -> Result<(), GameError> {
     
-    let sprite_sheet = SpriteSheet::new();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let mut options: Options = Options::parse(&args)?;
+    if options.help {
+        println!("{}", options::USAGE);
+        return Ok(());
+    }
     
-    let level: Level = Level::new_with_image(&Image::new_from_file(MAP_PATH).unwrap(), &sprite_sheet);
+    // Pack the loose resources into a single archive for distribution, a broken archive already there doesn't matter
+    if let Some(ref out_path) = options.pack_assets_path {
+        let loose_assets = Assets::new_loose();
+        Archive::write(std::path::Path::new(out_path), &loose_assets, &[&options.map_path, sprite_sheet::SPRITESHEET_PATH, FONT_PATH])?;
+        return Ok(());
+    }
     
-    let mut window = match RenderWindow::new(VideoMode::new_init(((level.size.x as u32) * GAME_SIZE),
-                                                                 ((level.size.y as u32) * GAME_SIZE), 32),
-                                            "Rust-Man",
-                                            Close,
-                                            &ContextSettings::default()) {
-
-        Some(window) => window,
-        None => panic!("Cannot create a new Window.")
+    let mut assets: Assets = Assets::new()?;
     
+    let mut sprite_sheet = SpriteSheet::new(&mut assets)?;
+    
+    // Report every problem with a map, exiting with 1 if there were any
+    if let Some(ref map_path) = options.validate_map_path {
+        let map_image: Image = map_format::load_map(&assets, map_path)?;
+        let problems = validate::validate_level(&Level::new_with_image(&map_image, &sprite_sheet));
+        for problem in problems.iter() {
+            println!("{}: {}", map_path, problem);
+        }
+        if problems.is_empty() {
+            println!("{} is valid", map_path);
+        }
+        std::process::exit(if problems.is_empty() { 0 } else { 1 });
+    }
+    
+    let mut map_image: Image = map_format::load_map(&assets, &options.map_path)?;
+    
+    // Headless mode for driving the game from another process
+    if options.env {
+        let mut env = Env::new(&map_image, &sprite_sheet);
+        env::run_stdio(&mut env);
+        return Ok(());
+    }
+    
+    // Render a single frame to an image, for tools and golden image checks
+    if let Some(frames) = options.render_frame {
+        let golden_path = options.golden_path.as_deref();
+        return screenshot::render_frame_to_file(&map_image, &sprite_sheet, frames, &options.out_path, golden_path);
+    }
+    
+    // A replay has to start the same way it was recorded
+    let mut replay: Option<Replay> = match options.playback_path {
+        Some(ref playback_path) => Some(Replay::load(playback_path)?),
+        None => None
     };
-    window.set_key_repeat_enabled(false);
+    if let Some(ref replay) = replay {
+        options.seed = replay.start.seed;
+        options.level = replay.start.level;
+        options.lives = replay.start.lives;
+        options.pre_turn = replay.start.pre_turn;
+        options.post_turn = replay.start.post_turn;
+    }
+    let replay_start = ReplayStart { seed: options.seed, level: options.level, lives: options.lives,
+                                     pre_turn: options.pre_turn, post_turn: options.post_turn };
+    let mut recorder: Option<ReplayRecorder> = match options.record_path {
+        Some(ref record_path) => Some(ReplayRecorder::create(record_path, &replay_start)?),
+        None => None
+    };
+    
+    let mut startup_state: Option<SaveState> = if options.load_state {
+        Some(SaveState::load(&options.state_path)?)
+    } else {
+        None
+    };
+    
+    let mut startup_script: Option<Script> = match options.script_path {
+        Some(ref script_path) => Some(Script::load(script_path)?),
+        None => None
+    };
+    
+    if options.headless {
+        // Every game after the first loads the script again so none of them shares its state
+        let new_headless_game = |game_index: u32| -> Result<Game, GameError> {
+            let start = ReplayStart { seed: replay_start.seed.wrapping_add(game_index as u64), ..replay_start.clone() };
+            let mut game = new_game(&map_image, &sprite_sheet, &start);
+            let script = match startup_script.take() {
+                Some(script) => Some(script),
+                None => match options.script_path {
+                    Some(ref script_path) => Some(Script::load(script_path)?),
+                    None => None
+                }
+            };
+            if let Some(script) = script {
+                game.set_script(script);
+            }
+            if let Some(ref state) = startup_state {
+                game.load_save_state(state)?;
+            }
+            Ok(game)
+        };
+        std::process::exit(headless::run(new_headless_game, replay, recorder, options.frames, options.games)?);
+    }
+    
+    let mut display: Display = Display::new(map_image.get_size(), options.scale)?;
+    display.fullscreen = options.fullscreen;
+    display.frame_cap = options.frame_cap;
+    display.vsync = options.vsync;
+    let mut window: RenderWindow = display.create_window()?;
     
 
+    let sound_bank = SoundBank::new(&assets);
+    let mut audio: Audio = Audio::new(&sound_bank);
     
-    let mut player: Player = Player::new(((level.size.x as f32) * GAME_SIZE as f32) / 2.0,
-                                         ((level.size.y as f32) * GAME_SIZE as f32) / 2.0,
-                                         sprite_sheet.generate_foreground_sprites(&SpriteType::PLAYER));
     let mut input: Input = Input::new();
+    let mut bot: Bot = Bot::new();
+    let mut autoplay = false;
     let mut game_time: GameTime = GameTime::new();
+    // Clock the simulation runs on, advanced by whole fixed steps
+    let mut simulation_time: GameTime = GameTime::new();
+    // Key presses are held until the next fixed update so none are lost
+    let mut pending_action = MoveDirection::NONE;
+    // The second player in co-op or versus
+    let second_device = if options.gamepad { Device::GAMEPAD(0) } else { Device::KEYS(&input::ARROWS) };
+    let mut pending_second_action = MoveDirection::NONE;
 
-    let font: Font = match Font::new_from_file("res/fonts/arial.ttf") {
-        Some(font) => font,
-        None => panic!("Could not load arial font!")
-    };
+    let font: Rc<AssetFont> = assets.font(FONT_PATH)?;
 
     let mut fps_text: Text = Text::new_init(&format!("FPS: {}", TARGET_FPS), &font, 10).unwrap(); 
     fps_text.set_color(&Color::yellow());
     
+    // Dev mode rebuilds the game whenever the map, sprite sheet or script is saved
+    let mut file_watcher = if options.dev {
+        let mut watched_paths = vec![options.map_path.as_str(), sprite_sheet::SPRITESHEET_PATH];
+        if let Some(ref script_path) = options.script_path {
+            watched_paths.push(script_path);
+        }
+        Some(FileWatcher::new(&assets, &watched_paths))
+    } else {
+        None
+    };
+    let mut changed_files: Vec<String> = vec![];
+    let mut reload_state = None;
     
+    let mut debug_overlay: DebugOverlay = DebugOverlay::new();
+    let mut frame_stats: FrameStats = FrameStats::new();
+    let mut frame_graph: VertexArray = VertexArray::new_init(PrimitiveType::Lines, 0).unwrap();
+    let mut stepping = false;
+    let mut paused_by_focus = false;
+    let mut pause_text: Text = Text::new_init("PAUSED", &font, 20).unwrap();
+    pause_text.set_color(&Color::yellow());
+    pause_text.set_position2f(0.0, 40.0);
+    let mut time_scale_text: Text = Text::new_init("", &font, 10).unwrap();
+    time_scale_text.set_color(&Color::yellow());
+    time_scale_text.set_position2f(0.0, 64.0);
+    
+    let mut editor: Option<Editor> = None;
+    let mut editor_text: Text = Text::new_init("", &font, 10).unwrap();
+    editor_text.set_color(&Color::white());
+    editor_text.set_position2f(0.0, 12.0);
+    
+    let mut two_player: Option<TwoPlayer> = None;
+    let mut ready_text: Text = Text::new_init("", &font, 20).unwrap();
+    ready_text.set_color(&Color::yellow());
+    ready_text.set_position2f(0.0, 40.0);
+    let mut scores_text: Text = Text::new_init("", &font, 10).unwrap();
+    scores_text.set_color(&Color::white());
+    scores_text.set_position2f(0.0, 12.0);
 
-    while window.is_open() {
-
-        // Start calculating new time data
-        game_time.previous_frame_time = game_time.start_frame_time;
-        game_time.start_frame_time = game_time.get_time_in_ms();
-
-        game_time.elapsed_time = game_time.start_frame_time - game_time.previous_frame_time;
+    let map_hash = net::get_map_hash(&map_image);
+    let mut net: Option<NetSession> = if let Some(port) = options.net_host {
+        Some(NetSession::host(port, options.seed, map_hash)?)
+    } else if let Some(ref address) = options.net_join {
+        Some(NetSession::join(address, Role::GUEST, options.seed, map_hash)?)
+    } else if let Some(ref address) = options.spectate {
+        Some(NetSession::join(address, Role::SPECTATOR, options.seed, map_hash)?)
+    } else {
+        None
+    };
 
-        game_time.delta_time = game_time.elapsed_time as units::DT;
-        game_time.fixed_time += game_time.elapsed_time as units::DT;
-        game_time.ticks += 1;
+    // With nothing else asked for, the bot plays a silent demo until Enter starts a game
+    let mut attract_mode = replay.is_none() && recorder.is_none() && net.is_none() && !options.load_state;
+    let mut attract_text: Text = Text::new_init("PRESS ENTER", &font, 20).unwrap();
+    attract_text.set_color(&Color::yellow());
+    attract_text.set_position2f(0.0, 40.0);
+    if !attract_mode {
+        audio.play_intro();
+    }
+    let mut game_over_text: Text = Text::new_init("GAME OVER", &font, 20).unwrap();
+    game_over_text.set_color(&Color::red());
+    game_over_text.set_position2f(0.0, 40.0);
+    // Only a game played by hand starts over, recordings and network games end where they are
+    let can_restart = replay.is_none() && recorder.is_none() && net.is_none();
 
-        input.clear_input();
+    'reload: loop {
 
-        // Input
-        for event in window.events() {
-            match event {
-                event::Closed => window.close(),
-                _ => { /* Nothing */ }
+        // The game borrows the resources, so they can only be swapped while it doesn't exist.
+        // Eaten pellets are only thrown away when a new map actually loaded.
+        let mut map_changed = false;
+        let mut script_changed = false;
+        for changed_file in changed_files.drain(..) {
+            if changed_file == options.map_path {
+                match map_format::load_map(&assets, &options.map_path) {
+                    Ok(image) => {
+                        map_image = image;
+                        map_changed = true;
+                        eprintln!("Reloaded {}", changed_file);
+                    },
+                    Err(error) => eprintln!("Keeping the old map: {}", error)
+                }
+            } else if changed_file == sprite_sheet::SPRITESHEET_PATH {
+                assets.forget_texture(sprite_sheet::SPRITESHEET_PATH);
+                match SpriteSheet::new(&mut assets) {
+                    Ok(new_sprite_sheet) => {
+                        sprite_sheet = new_sprite_sheet;
+                        eprintln!("Reloaded {}", changed_file);
+                    },
+                    Err(error) => eprintln!("Keeping the old sprite sheet: {}", error)
+                }
+            } else {
+                script_changed = true;
             }
-            input.check_input(event);
         }
-        
-        if input.is_key_down(Key::Escape) {
-            window.close();
+
+        let mut game: Game = new_game(&map_image, &sprite_sheet, &replay_start);
+        if options.party.is_some() && !attract_mode {
+            game.set_party(options.party.clone());
+        }
+        // Rebuilding the game loads the script again, picking up any edits to it
+        let script = match startup_script.take() {
+            Some(script) => Some(script),
+            None => options.script_path.as_ref().and_then(|script_path| match Script::load(script_path) {
+                Ok(script) => {
+                    if script_changed {
+                        eprintln!("Reloaded {}", script_path);
+                    }
+                    Some(script)
+                },
+                Err(error) => {
+                    eprintln!("Carrying on without the script: {}", error);
+                    None
+                }
+            })
+        };
+        if let Some(script) = script {
+            game.set_script(script);
+        }
+        if let Some(state) = startup_state.take() {
+            game.load_save_state(&state)?;
+        }
+        if options.two_player && two_player.is_none() && !attract_mode {
+            two_player = Some(TwoPlayer::new(&game, options.separate_controls));
+        }
+        if let Some(state) = reload_state.take() {
+            game.restore_reload_state(state, map_changed);
+            display.set_level_size(&mut window, game.level.size)?;
+            // Edit history doesn't survive the level being rebuilt
+            if editor.is_some() {
+                editor = Some(Editor::new(&game.level));
+            }
         }
 
 
-        // Update
-        player.update(&input, &game_time, &level);
+        while window.is_open() {
 
-        // Fixed Update
-        while game_time.fixed_time >= MS_PER_UPDATE as units::DT {
-            game_time.fixed_ticks += 1;
-            game_time.fixed_time -= MS_PER_UPDATE as units::DT;
-            // FixedUpdate()
-        }
+            // Start calculating new time data
+            game_time.begin_frame(MS_PER_UPDATE * MAX_UPDATES_PER_FRAME as units::DT);
+            frame_stats.push(game_time.elapsed_us);
 
-        // Rendering
-        window.clear(&Color::black());
-        window.draw(&level);
-        window.draw(&player);
-        window.draw(&fps_text);
-        window.display();
-
-        // VSYNC
-/*
-        if game_time.elapsed_time < MS_PER_FRAME {
-            sf::sleep(sf::Time::with_milliseconds(game_time.start_frame_time + MS_PER_FRAME - game_time.get_time_in_ms()));
-        }
-*/
-        if game_time.elapsed_time != 0 {
-            game_time.fps = 1000 / game_time.elapsed_time;
-        }
+            input.clear_input();
+
+            // Input
+            let events: Vec<event::Event> = window.events().collect();
+            for event in events {
+                match event {
+                    event::Closed => window.close(),
+                    event::Resized { width, height } => display.resize(&mut window, width, height),
+                    // Only resume on focus if it was losing focus that paused the game
+                    event::LostFocus if !game_time.paused => {
+                        paused_by_focus = true;
+                        set_paused(&mut game_time, &mut audio, true);
+                    },
+                    event::GainedFocus if paused_by_focus => {
+                        paused_by_focus = false;
+                        set_paused(&mut game_time, &mut audio, false);
+                    },
+                    event::MouseButtonPressed { button, x, y } => {
+                        if let Some(ref mut editor) = editor {
+                            let tile = get_mouse_tile(&display, &window, &game.level, x, y);
+                            editor.start_stroke(&mut game.level, tile, button == MouseButton::MouseRight);
+                        }
+                    },
+                    event::MouseMoved { x, y } => {
+                        if let Some(ref mut editor) = editor {
+                            let tile = get_mouse_tile(&display, &window, &game.level, x, y);
+                            editor.move_cursor(&mut game.level, tile);
+                        }
+                    },
+                    event::MouseButtonReleased { .. } => {
+                        if let Some(ref mut editor) = editor {
+                            editor.end_stroke();
+                        }
+                    },
+                    _ => { /* Nothing */ }
+                }
+                input.check_input(event);
+            }
+
+            if let Some(ref mut net) = net {
+                net.poll();
+            }
+
+            if input.is_key_down(Key::Escape) {
+                window.close();
+            }
+
+            if attract_mode && input.is_key_down(Key::Return) {
+                attract_mode = false;
+                audio.play_intro();
+                continue 'reload;
+            }
+            if can_restart && !attract_mode && game.is_game_over() && input.is_key_down(Key::Return) {
+                two_player = None;
+                audio.play_intro();
+                continue 'reload;
+            }
+
+            if input.is_key_down(Key::F11) {
+                display.toggle_fullscreen(&mut window)?;
+            }
+
+            if input.is_key_down(Key::F10) {
+                display.scanlines_enabled = !display.scanlines_enabled;
+            }
+
+            if input.is_key_down(Key::F12) {
+                match screenshot::save_screenshot(&game) {
+                    Some(file_name) => println!("Saved screenshot to {}", file_name),
+                    None => eprintln!("Could not save screenshot")
+                }
+            }
+
+            if input.is_key_down(Key::M) {
+                audio.toggle_mute();
+            }
+
+            if input.is_key_down(Key::Tab) {
+                autoplay = !autoplay;
+            }
+
+            if input.is_key_down(Key::P) {
+                paused_by_focus = false;
+                let paused = !game_time.paused;
+                set_paused(&mut game_time, &mut audio, paused);
+            }
+
+            if input.is_key_down(Key::LBracket) {
+                game_time.time_scale = (game_time.time_scale / 2.0).max(MIN_TIME_SCALE);
+            }
+            if input.is_key_down(Key::RBracket) {
+                game_time.time_scale = (game_time.time_scale * 2.0).min(MAX_TIME_SCALE);
+            }
+
+            // Only the world units change, the view keeps the maze the same size on screen
+            if options.dev && input.is_key_down(Key::Dash) {
+                let tile_size = (game.level.get_tile_size() / 2.0).max(MIN_TILE_SIZE);
+                game.set_tile_size(tile_size);
+            }
+            if options.dev && input.is_key_down(Key::Equal) {
+                let tile_size = (game.level.get_tile_size() * 2.0).min(MAX_TILE_SIZE);
+                game.set_tile_size(tile_size);
+            }
+
+            if input.is_key_down(Key::F4) {
+                debug_overlay.enabled = !debug_overlay.enabled;
+            }
+
+            // While stepping the simulation only advances one fixed update per press of F7
+            if input.is_key_down(Key::F6) {
+                stepping = !stepping;
+            }
+            if stepping {
+                game_time.fixed_time = if input.is_key_down(Key::F7) { MS_PER_UPDATE } else { 0.0 };
+            }
+
+            // Quick save and load, left alone while editing as loading would undo the edits.
+            // Loading would also leave a network game out of step with the other end.
+            if editor.is_none() && input.is_key_down(Key::F5) {
+                match game.get_save_state().write(&options.state_path) {
+                    Ok(()) => println!("Saved state to {}", options.state_path),
+                    Err(error) => eprintln!("{}", error)
+                }
+            }
+            if editor.is_none() && net.is_none() && input.is_key_down(Key::F9) {
+                match SaveState::load(&options.state_path).and_then(|state| game.load_save_state(&state)) {
+                    Ok(()) => println!("Loaded state from {}", options.state_path),
+                    Err(error) => eprintln!("{}", error)
+                }
+            }
+
+            // The editor pauses the game, leaving it restarts the level with the edits
+            if net.is_none() && !attract_mode && input.is_key_down(Key::F1) {
+                editor = match editor {
+                    Some(_) => {
+                        game.on_level_edited();
+                        None
+                    },
+                    None => Some(Editor::new(&game.level))
+                };
+            }
+
+            if let Some(ref mut editor) = editor {
+                for (key, brush) in EDITOR_BRUSH_KEYS.iter().zip(editor::BRUSHES.iter()) {
+                    if input.is_key_down(*key) {
+                        editor.brush = brush.clone();
+                    }
+                }
+                if input.is_key_down(Key::Z) {
+                    editor.undo(&mut game.level);
+                }
+                if input.is_key_down(Key::Y) {
+                    editor.redo(&mut game.level);
+                }
+                let save_extension = if input.is_key_down(Key::F2) {
+                    Some("png")
+                } else if input.is_key_down(Key::F3) {
+                    Some("txt")
+                } else {
+                    None
+                };
+                if let Some(save_extension) = save_extension {
+                    let map_file = assets.resolve_file(&options.map_path).unwrap_or(PathBuf::from(&options.map_path));
+                    let save_path = map_file.with_extension(save_extension);
+                    // Overwriting a file takes a second press of the same key, and keeps the old file as a backup
+                    if save_path.is_file() && editor.pending_overwrite.as_ref() != Some(&save_path) {
+                        editor.pending_overwrite = Some(save_path);
+                    } else {
+                        editor.pending_overwrite = None;
+                        match map_format::backup_file(&save_path).and_then(|_| map_format::save_level(&game.level, &save_path)) {
+                            Ok(()) => {
+                                editor.unsaved = false;
+                                println!("Saved map to {}", save_path.display());
+                            },
+                            Err(error) => eprintln!("{}", error)
+                        }
+                    }
+                }
+                game_time.fixed_time = 0.0;
+            }
+
+
+            // Update
+            let bindings = two_player.as_ref().map(|two_player| two_player.get_bindings()).unwrap_or(&input::WASD);
+            let key_action = input.get_move_direction(bindings);
+            if key_action != MoveDirection::NONE {
+                pending_action = key_action;
+            }
+            if options.party.is_some() && net.is_none() {
+                let second_action = input.get_device_direction(&second_device);
+                if second_action != MoveDirection::NONE {
+                    pending_second_action = second_action;
+                }
+            }
 
-        if game_time.ticks % 100 == 0 {
-            let player_pos = player.sprite.sfml_sprite.get_position();
-            fps_text.set_string(&format!("FPS: {} - Player Pos: {}, {}", game_time.fps, player_pos.x, player_pos.y));
+            // Nothing moves while the ready screen shows
+            if let Some(ref mut two_player) = two_player {
+                two_player.update(game_time.delta_time);
+                if two_player.is_showing_ready() {
+                    game_time.fixed_time = 0.0;
+                }
+            }
+
+            // Fixed Update
+            let mut updates = 0;
+            if game.is_game_over() {
+                game_time.fixed_time = 0.0;
+            }
+            while editor.is_none() && game_time.fixed_time >= MS_PER_UPDATE && updates < MAX_UPDATES_PER_FRAME {
+                updates += 1;
+                // Playback holds on the last frame the recording ran
+                if replay.as_ref().map(|replay| replay.is_finished(simulation_time.ticks as u32 + 1)).unwrap_or(false) {
+                    game_time.fixed_time = 0.0;
+                    break;
+                }
+                // Over the network a tick only runs once both players' inputs for it are known
+                let mut network_inputs = None;
+                if let Some(ref mut net) = net {
+                    let next_tick = simulation_time.ticks as u32 + 1;
+                    if net.needs_local_input(next_tick) {
+                        let local_action = if autoplay && net.get_local_slot() == Some(0) {
+                            bot.get_action(game.get_player_mover(), &game.level, &game.get_threats())
+                        } else {
+                            std::mem::replace(&mut pending_action, MoveDirection::NONE)
+                        };
+                        net.queue_local_input(next_tick, local_action);
+                    }
+                    network_inputs = net.get_inputs(next_tick);
+                    if network_inputs.is_none() {
+                        // Don't build up steps to race through once the inputs arrive
+                        game_time.fixed_time = game_time.fixed_time.min(MS_PER_UPDATE);
+                        break;
+                    }
+                }
+
+                game_time.fixed_ticks += 1;
+                game_time.fixed_time -= MS_PER_UPDATE;
+
+                simulation_time.advance(MS_PER_UPDATE);
+                let tick = simulation_time.ticks as u32;
+                let action = if let Some(ref inputs) = network_inputs {
+                    inputs[0].clone()
+                } else if let Some(ref mut replay) = replay {
+                    replay.get_action(tick)
+                } else if autoplay || attract_mode {
+                    bot.get_action(game.get_player_mover(), &game.level, &game.get_threats())
+                } else {
+                    std::mem::replace(&mut pending_action, MoveDirection::NONE)
+                };
+                if let Some(ref mut recorder) = recorder {
+                    recorder.record(tick, &action);
+                }
+                let second_action = match network_inputs {
+                    Some(ref inputs) => inputs[1].clone(),
+                    None => std::mem::replace(&mut pending_second_action, MoveDirection::NONE)
+                };
+                game.set_action(1, second_action);
+                if attract_mode {
+                    game.update(action, &simulation_time, &mut ());
+                } else {
+                    game.update(action, &simulation_time, &mut audio);
+                }
+                if let (Some(ref mut net), Some(inputs)) = (net.as_mut(), network_inputs) {
+                    net.on_tick(tick, inputs, &game);
+                }
+
+                if let Some(ref mut two_player) = two_player {
+                    if game.events.contains(&GameEvent::PlayerDied) {
+                        two_player.on_player_died(&mut game);
+                        // Stop catching up so the ready screen isn't skipped
+                        game_time.fixed_time = 0.0;
+                    }
+                }
+
+                // The demo starts over rather than going on to the next level
+                if attract_mode && (game.is_game_over() || game.events.contains(&GameEvent::LevelCleared)) {
+                    continue 'reload;
+                }
+                if game.is_game_over() {
+                    break;
+                }
+
+                if game.events.contains(&GameEvent::LevelCleared) {
+                    game.next_level();
+                    audio.play_intro();
+                }
+            }
+            if let Some(ref mut net) = net {
+                net.send();
+            }
+            // The siren would restart itself while paused
+            if !game_time.paused && !attract_mode {
+                audio.update(game.get_progress());
+            }
+
+            // Rendering
+            match editor {
+                Some(ref editor) => {
+                    display.draw(&mut window, &Layered(&game, editor), &game.level.get_world_rect());
+                    editor_text.set_string(&editor.get_status());
+                    window.draw(&editor_text);
+                },
+                None => {
+                    if debug_overlay.enabled {
+                        debug_overlay.update(&game, if autoplay || attract_mode { Some(&bot) } else { None });
+                    }
+                    display.draw(&mut window, &Layered(&game, &debug_overlay), &game.level.get_world_rect());
+                }
+            }
+            if debug_overlay.enabled {
+                frame_graph.clear();
+                let window_height = window.get_size().y as f32;
+                frame_stats.append_graph(&mut frame_graph, Vector2f::new(0.0, window_height), FRAME_GRAPH_PIXELS_PER_MS);
+                window.draw(&frame_graph);
+            }
+            if game_time.paused {
+                window.draw(&pause_text);
+            }
+            if game_time.time_scale != 1.0 {
+                time_scale_text.set_string(&format!("SPEED x{}", game_time.time_scale));
+                window.draw(&time_scale_text);
+            }
+            if attract_mode {
+                window.draw(&attract_text);
+            } else if game.is_game_over() {
+                window.draw(&game_over_text);
+            }
+            if let Some(ref two_player) = two_player {
+                if two_player.is_showing_ready() && !game_time.paused {
+                    ready_text.set_string(two_player.get_ready_text());
+                    window.draw(&ready_text);
+                }
+                if editor.is_none() {
+                    scores_text.set_string(&two_player.get_scores_text(&game));
+                    window.draw(&scores_text);
+                }
+            }
+            if let Some(status) = net.as_ref().and_then(|net| net.get_status()) {
+                ready_text.set_string(&status);
+                window.draw(&ready_text);
+            }
+            window.draw(&fps_text);
+            // Waits here for vsync or the frame cap, if either is enabled
+            window.display();
+
+            if game_time.ticks % 100 == 0 {
+                let player_pos = game.get_player_position();
+                fps_text.set_string(&format!("FPS: {:.0} (1% low {:.0}, {:.2}ms) - Player Pos: {}, {}",
+                                             frame_stats.get_average_fps(), frame_stats.get_one_percent_low_fps(),
+                                             frame_stats.get_average_ms(), player_pos.x, player_pos.y));
+            }
+
+            if let Some(ref mut file_watcher) = file_watcher {
+                changed_files = file_watcher.poll(game_time.elapsed_us as units::DT / 1000.0);
+            }
+            if !changed_files.is_empty() {
+                reload_state = Some(game.into_reload_state());
+                continue 'reload;
+            }
+        }
+        if let Some(recorder) = recorder.take() {
+            recorder.finish(simulation_time.ticks as u32, game.score.points)?;
         }
+        break;
     }
-}
\ No newline at end of file
+    Ok(())
+}
+
+fn set_paused(game_time: &mut GameTime, audio: &mut Audio, paused: bool) {
+    game_time.paused = paused;
+    audio.set_paused(paused);
+}
+
+fn get_mouse_tile(display: &Display, window: &RenderWindow, level: &Level, x: i32, y: i32) -> (i32, i32) {
+    let world_position = display.window_to_world(window, x, y, &level.get_world_rect());
+    level.world_to_tile(world_position.x, world_position.y)
+}
+
+fn new_game<'s>(map_image: &'s Image, sprite_sheet: &'s SpriteSheet, start: &ReplayStart) -> Game<'s> {
+    let mut game = Game::new(map_image, sprite_sheet, start.seed);
+    game.level_number = start.level;
+    game.score.lives = start.lives;
+    game.set_cornering(start.pre_turn, start.post_turn);
+    return game;
+}
@@ -0,0 +1,207 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use sfml::graphics::{Image, Color};
+
+use level::Level;
+use level_object::LevelType;
+use error::GameError;
+use resources::Assets;
+
+// Any other colour is empty space. The original map drew its corridors black, so black is a pellet.
+const SPACE_COLOR: Color = Color { red: 128, green: 128, blue: 128, alpha: 255 };
+const WALL_COLOR: Color = Color { red: 0, green: 0, blue: 255, alpha: 255 };
+const PELLET_COLOR: Color = Color { red: 0, green: 0, blue: 0, alpha: 255 };
+const POWERPELLET_COLOR: Color = Color { red: 255, green: 255, blue: 255, alpha: 255 };
+const PLAYER_SPAWN_COLOR: Color = Color { red: 255, green: 255, blue: 0, alpha: 255 };
+
+// Text maps have one character per tile and one line per row
+const SPACE_CHAR: char = ' ';
+const WALL_CHAR: char = '#';
+const PELLET_CHAR: char = '.';
+const POWERPELLET_CHAR: char = 'o';
+const PLAYER_SPAWN_CHAR: char = 'P';
+
+/// Loads a map image, converting it first if the path is a `.txt` text map
+pub fn load_map(assets: &Assets, path: &str) -> Result<Image, GameError> {
+    if !is_text_map(path) {
+        return assets.image(path);
+    }
+    let text = assets.text(path)?;
+    text_to_image(&text).ok_or(GameError::InvalidResource { path: Path::new(path).to_path_buf() })
+}
+
+pub fn is_text_map(path: &str) -> bool {
+    Path::new(path).extension().map(|extension| extension == "txt").unwrap_or(false)
+}
+
+pub fn level_to_image(level: &Level) -> Option<Image> {
+    let mut image = Image::new(level.size.x, level.size.y)?;
+    for x in 0..level.size.x {
+        for y in 0..level.size.y {
+            let color = match level.get_tile(x as i32, y as i32).level_type {
+                LevelType::SPACE => &SPACE_COLOR,
+                LevelType::WALL => &WALL_COLOR,
+                LevelType::PELLET => &PELLET_COLOR,
+                LevelType::POWERPELLET => &POWERPELLET_COLOR
+            };
+            image.set_pixel(x, y, color);
+        }
+    }
+    if let Some((x, y)) = level.get_player_spawn() {
+        image.set_pixel(x as u32, y as u32, &PLAYER_SPAWN_COLOR);
+    }
+    return Some(image);
+}
+
+pub fn level_to_text(level: &Level) -> String {
+    let mut text = String::new();
+    for y in 0..level.size.y as i32 {
+        for x in 0..level.size.x as i32 {
+            text.push(if level.get_player_spawn() == Some((x, y)) {
+                PLAYER_SPAWN_CHAR
+            } else {
+                level_type_to_char(&level.get_tile(x, y).level_type)
+            });
+        }
+        text.push('\n');
+    }
+    return text;
+}
+
+pub fn level_type_to_char(level_type: &LevelType) -> char {
+    match *level_type {
+        LevelType::SPACE => SPACE_CHAR,
+        LevelType::WALL => WALL_CHAR,
+        LevelType::PELLET => PELLET_CHAR,
+        LevelType::POWERPELLET => POWERPELLET_CHAR
+    }
+}
+
+/// The tile a pixel of a map image stands for, and whether it marks the player's spawn
+pub fn color_to_level_type(color: &Color) -> (LevelType, bool) {
+    if *color == WALL_COLOR {
+        (LevelType::WALL, false)
+    } else if *color == PELLET_COLOR {
+        (LevelType::PELLET, false)
+    } else if *color == POWERPELLET_COLOR {
+        (LevelType::POWERPELLET, false)
+    } else {
+        (LevelType::SPACE, *color == PLAYER_SPAWN_COLOR)
+    }
+}
+
+/// Anything that isn't a wall or a pellet is empty space
+pub fn char_to_level_type(tile: char) -> LevelType {
+    match tile {
+        WALL_CHAR => LevelType::WALL,
+        PELLET_CHAR => LevelType::PELLET,
+        POWERPELLET_CHAR => LevelType::POWERPELLET,
+        _ => LevelType::SPACE
+    }
+}
+
+/// Builds the image form of a text map so it loads like any other, short lines are padded with space
+pub fn text_to_image(text: &str) -> Option<Image> {
+    let lines: Vec<&str> = text.lines().filter(|line| !line.trim().is_empty()).collect();
+    let width = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u32;
+    let height = lines.len() as u32;
+    if width == 0 {
+        return None;
+    }
+
+    let mut image = Image::new_from_color(width, height, &SPACE_COLOR)?;
+    for (y, line) in lines.iter().enumerate() {
+        for (x, tile) in line.chars().enumerate() {
+            let color = match tile {
+                WALL_CHAR => &WALL_COLOR,
+                PELLET_CHAR => &PELLET_COLOR,
+                POWERPELLET_CHAR => &POWERPELLET_COLOR,
+                PLAYER_SPAWN_CHAR => &PLAYER_SPAWN_COLOR,
+                _ => &SPACE_COLOR
+            };
+            image.set_pixel(x as u32, y as u32, color);
+        }
+    }
+    return Some(image);
+}
+
+/// Copies a file that is about to be overwritten to the same path with `.bak` added, if it exists
+pub fn backup_file(path: &Path) -> Result<(), GameError> {
+    if !path.is_file() {
+        return Ok(());
+    }
+    let mut backup_path = path.as_os_str().to_os_string();
+    backup_path.push(".bak");
+    fs::copy(path, &backup_path).map(|_| ()).map_err(|error| GameError::Io { path: PathBuf::from(backup_path), error: error })
+}
+
+/// Writes the level as a text map if the path ends in `.txt`, otherwise as an image
+pub fn save_level(level: &Level, path: &Path) -> Result<(), GameError> {
+    let failed = || GameError::InvalidResource { path: path.to_path_buf() };
+    let path_str = path.to_str().ok_or(failed())?;
+
+    if is_text_map(path_str) {
+        let mut file = File::create(path).map_err(|_| failed())?;
+        file.write_all(level_to_text(level).as_bytes()).map_err(|_| failed())
+    } else {
+        match level_to_image(level) {
+            Some(ref image) if image.save_to_file(path_str) => Ok(()),
+            _ => Err(failed())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use test_support::{sprite_sheet, map_image};
+
+    const ROWS: [&'static str; 3] = ["#####",
+                                     "#.oP#",
+                                     "#####"];
+
+    #[test]
+    fn text_maps_survive_a_round_trip() {
+        let sheet = sprite_sheet();
+        let level = Level::new_with_image(&map_image(&ROWS), &sheet);
+        assert_eq!(level_to_text(&level), ROWS.join("\n") + "\n");
+    }
+
+    #[test]
+    fn image_maps_survive_a_round_trip() {
+        let sheet = sprite_sheet();
+        let level = Level::new_with_image(&map_image(&ROWS), &sheet);
+        let reloaded = Level::new_with_image(&level_to_image(&level).unwrap(), &sheet);
+        assert_eq!(level_to_text(&reloaded), level_to_text(&level));
+        assert_eq!(reloaded.get_player_spawn(), Some((3, 1)));
+    }
+
+    #[test]
+    fn short_lines_and_unknown_characters_are_space() {
+        let image = text_to_image("###\n#x\n").unwrap();
+        assert_eq!((image.get_size().x, image.get_size().y), (3, 2));
+        assert_eq!(color_to_level_type(&image.get_pixel(1, 1)), (LevelType::SPACE, false));
+        assert_eq!(color_to_level_type(&image.get_pixel(2, 1)), (LevelType::SPACE, false));
+        assert_eq!(char_to_level_type('x'), LevelType::SPACE);
+        assert!(text_to_image("\n  \n").is_none());
+    }
+
+    #[test]
+    fn saving_over_a_map_keeps_a_backup() {
+        let sheet = sprite_sheet();
+        let level = Level::new_with_image(&map_image(&ROWS), &sheet);
+        let path = env::temp_dir().join("rustman-map-format-test.txt");
+        fs::write(&path, "old map").unwrap();
+
+        backup_file(&path).unwrap();
+        save_level(&level, &path).unwrap();
+
+        let mut backup_path = path.as_os_str().to_os_string();
+        backup_path.push(".bak");
+        assert_eq!(fs::read_to_string(&backup_path).unwrap(), "old map");
+        assert_eq!(fs::read_to_string(&path).unwrap(), level_to_text(&level));
+    }
+}
@@ -0,0 +1,606 @@
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::io::ErrorKind;
+use std::net::{UdpSocket, SocketAddr, ToSocketAddrs};
+use std::time::{Duration, Instant};
+
+use sfml::graphics::Image;
+
+use error::GameError;
+use game::Game;
+use player::{self, MoveDirection};
+
+// Each player's input is used this many ticks after it was pressed, giving it time to arrive
+const INPUT_DELAY_TICKS: u32 = 3;
+// Every packet repeats this many recent ticks, so a lost packet is covered by the next one
+const RESEND_TICKS: u32 = 30;
+// How often the players compare state hashes
+const HASH_INTERVAL_TICKS: u32 = 60;
+// Ticks from the other end are only taken this far past the last one that ran, anything further is bogus
+const MAX_TICKS_AHEAD: u32 = RESEND_TICKS * 2;
+const MAX_SPECTATORS: usize = 8;
+// How far back the host keeps ticks for spectators, five minutes. A spectator further behind is turned away.
+const MAX_SPECTATOR_LAG_TICKS: u32 = 60 * 60 * 5;
+// Anyone who has sent nothing for this long has gone
+const DISCONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_PACKET_SIZE: usize = 2048;
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Role {
+    /// Plays Pac-Man and forwards the game to spectators
+    HOST,
+    /// Plays the ghost
+    GUEST,
+    /// Watches the host's game without playing
+    SPECTATOR
+}
+
+/// Versus over UDP in lockstep. Both players run the same deterministic simulation and only exchange inputs,
+/// a tick runs once both inputs for it are known. State hashes are compared every so often to catch desyncs.
+/// Messages are single lines of text: `HELLO <player|spectator> <seed> <map hash>` answered by `WELCOME <cookie>` or
+/// `REFUSED <reason>`, `INPUTS <first tick> <direction>...`, `TICKS <first tick> <direction>,<direction>...` for spectators,
+/// `WANT <tick> <cookie>` from spectators and `HASH <tick> <hash>`. A spectator is only streamed to once it has echoed
+/// the cookie, so a HELLO from a forged address can't make the host send to it. Anyone quiet for DISCONNECT_TIMEOUT is dropped.
+pub struct NetSession {
+    pub role: Role,
+    /// Set once the states stopped matching, the game stops there
+    pub desynced: bool,
+    /// Set once the other end went quiet, the game stops there too
+    pub disconnected: bool,
+    /// Why the host turned this end away, the game never starts
+    pub refused: Option<String>,
+
+    socket: UdpSocket,
+    /// The other player, or the host for a spectator
+    remote: Option<SocketAddr>,
+    connected: bool,
+    /// When anything last arrived from `remote`
+    last_heard: Instant,
+    /// Both ends have to start from the same seed and map
+    seed: u64,
+    map_hash: u64,
+    /// Keys the host's cookies, which are a hash of the spectator's address so nothing is kept until it replies
+    cookie_keys: RandomState,
+    /// The cookie the host gave this spectator
+    cookie: Option<u64>,
+    /// Each spectator, the first tick it is still missing and when it last asked for it
+    spectators: Vec<(SocketAddr, u32, Instant)>,
+
+    local_inputs: HashMap<u32, MoveDirection>,
+    remote_inputs: HashMap<u32, MoveDirection>,
+    /// Both inputs of ticks that have run on the host, back to MAX_SPECTATOR_LAG_TICKS, or arrived from it on a spectator
+    confirmed_inputs: HashMap<u32, [MoveDirection; 2]>,
+    last_queued_tick: u32,
+    last_run_tick: u32,
+
+    local_hashes: HashMap<u32, u64>,
+    remote_hashes: HashMap<u32, u64>
+}
+
+impl NetSession {
+    /// Waits for a guest on `port`, turning away anyone on a different seed or map
+    pub fn host(port: u16, seed: u64, map_hash: u64) -> Result<NetSession, GameError> {
+        let address = format!("0.0.0.0:{}", port);
+        let socket = UdpSocket::bind(&address).map_err(|_| GameError::Network { address: address })?;
+        NetSession::new(Role::HOST, socket, None, seed, map_hash)
+    }
+
+    /// Connects to a host as the guest player, or as a spectator
+    pub fn join(address: &str, role: Role, seed: u64, map_hash: u64) -> Result<NetSession, GameError> {
+        let error = || GameError::Network { address: address.to_string() };
+        let remote = address.to_socket_addrs().ok().and_then(|mut addresses| addresses.next()).ok_or(error())?;
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(|_| error())?;
+        NetSession::new(role, socket, Some(remote), seed, map_hash)
+    }
+
+    fn new(role: Role, socket: UdpSocket, remote: Option<SocketAddr>, seed: u64, map_hash: u64) -> Result<NetSession, GameError> {
+        let address = remote.map(|remote| remote.to_string()).unwrap_or_default();
+        socket.set_nonblocking(true).map_err(|_| GameError::Network { address: address })?;
+        Ok(NetSession {
+            role: role,
+            desynced: false,
+            disconnected: false,
+            refused: None,
+            socket: socket,
+            remote: remote,
+            connected: false,
+            last_heard: Instant::now(),
+            seed: seed,
+            map_hash: map_hash,
+            cookie_keys: RandomState::new(),
+            cookie: None,
+            spectators: vec![],
+            local_inputs: HashMap::new(),
+            remote_inputs: HashMap::new(),
+            confirmed_inputs: HashMap::new(),
+            last_queued_tick: INPUT_DELAY_TICKS,
+            last_run_tick: 0,
+            local_hashes: HashMap::new(),
+            remote_hashes: HashMap::new()
+        })
+    }
+
+    /// Which player this end steers, None for a spectator
+    pub fn get_local_slot(&self) -> Option<usize> {
+        match self.role {
+            Role::HOST => Some(0),
+            Role::GUEST => Some(1),
+            Role::SPECTATOR => None
+        }
+    }
+
+    /// Reads every packet that has arrived
+    pub fn poll(&mut self) {
+        let mut buffer = [0; MAX_PACKET_SIZE];
+        loop {
+            let (length, sender) = match self.socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(ref error) if error.kind() == ErrorKind::WouldBlock => break,
+                // Connection resets show up here when the other end isn't listening yet, skip them
+                Err(_) => continue
+            };
+            if let Ok(message) = String::from_utf8(buffer[..length].to_vec()) {
+                self.on_message(&message, sender);
+            }
+        }
+        self.check_hashes();
+        self.check_timeouts(Instant::now());
+    }
+
+    // Drops spectators that stopped asking for ticks, and gives up on the other player or the host once they go quiet
+    fn check_timeouts(&mut self, now: Instant) {
+        let timed_out = |heard: Instant| now.duration_since(heard) > DISCONNECT_TIMEOUT;
+        self.spectators.retain(|&(spectator, _, heard)| {
+            if timed_out(heard) {
+                eprintln!("Spectator {} left", spectator);
+            }
+            !timed_out(heard)
+        });
+        if self.connected && !self.disconnected && timed_out(self.last_heard) {
+            eprintln!("Lost the connection after tick {}", self.last_run_tick);
+            self.disconnected = true;
+        }
+    }
+
+    fn on_message(&mut self, message: &str, sender: SocketAddr) {
+        let words: Vec<&str> = message.split_whitespace().collect();
+        match (self.role.clone(), words.first().cloned()) {
+            (Role::HOST, Some("HELLO")) => {
+                self.on_hello(&words, sender);
+                return;
+            },
+            (Role::HOST, Some("WANT")) => {
+                self.on_want(&words, sender);
+                return;
+            },
+            _ => { }
+        }
+
+        // Everything else only counts from whoever this end is playing with
+        if self.remote != Some(sender) {
+            return;
+        }
+        self.last_heard = Instant::now();
+        match words.first().cloned() {
+            Some("WELCOME") => {
+                self.cookie = words.get(1).and_then(|cookie| cookie.parse::<u64>().ok());
+                self.connected = true;
+                return;
+            },
+            Some("REFUSED") => {
+                if self.refused.is_none() {
+                    let reason = words[1..].join(" ");
+                    eprintln!("The host refused to connect: {}", reason);
+                    self.refused = Some(reason);
+                }
+                return;
+            },
+            _ => { }
+        }
+        self.connected = true;
+
+        let first_tick = words.get(1).and_then(|tick| tick.parse::<u32>().ok());
+        match (words.first().cloned(), first_tick) {
+            (Some("INPUTS"), Some(first_tick)) => {
+                for (index, word) in words[2..].iter().enumerate().take(RESEND_TICKS as usize) {
+                    let tick = first_tick.checked_add(index as u32).filter(|&tick| self.is_in_window(tick));
+                    if let (Some(tick), Some(action)) = (tick, player::parse_action(word)) {
+                        self.remote_inputs.insert(tick, action);
+                    }
+                }
+            },
+            (Some("TICKS"), Some(first_tick)) => {
+                for (index, word) in words[2..].iter().enumerate().take(RESEND_TICKS as usize) {
+                    let tick = match first_tick.checked_add(index as u32).filter(|&tick| self.is_in_window(tick)) {
+                        Some(tick) => tick,
+                        None => continue
+                    };
+                    let mut actions = word.split(',').filter_map(player::parse_action);
+                    if let (Some(first), Some(second)) = (actions.next(), actions.next()) {
+                        self.confirmed_inputs.insert(tick, [first, second]);
+                    }
+                }
+            },
+            (Some("HASH"), Some(tick)) => {
+                if !tick.is_multiple_of(HASH_INTERVAL_TICKS) || tick > self.last_run_tick.saturating_add(MAX_TICKS_AHEAD) {
+                    return;
+                }
+                if let Some(hash) = words.get(2).and_then(|hash| hash.parse::<u64>().ok()) {
+                    self.remote_hashes.insert(tick, hash);
+                }
+            },
+            _ => { }
+        }
+    }
+
+    // Answers a player or spectator asking to join, with a cookie to echo back or the reason they can't
+    fn on_hello(&mut self, words: &[&str], sender: SocketAddr) {
+        let seed = words.get(2).and_then(|seed| seed.parse::<u64>().ok());
+        let map_hash = words.get(3).and_then(|map_hash| map_hash.parse::<u64>().ok());
+        let refusal = if seed != Some(self.seed) {
+            Some("different seed")
+        } else if map_hash != Some(self.map_hash) {
+            Some("different map")
+        } else if words.get(1) == Some(&"spectator") {
+            let known = self.spectators.iter().any(|&(spectator, _, _)| spectator == sender);
+            if !known && self.spectators.len() >= MAX_SPECTATORS { Some("too many spectators") } else { None }
+        } else if self.remote.is_some() && self.remote != Some(sender) {
+            Some("game full")
+        } else {
+            None
+        };
+
+        if let Some(refusal) = refusal {
+            let _ = self.socket.send_to(format!("REFUSED {}", refusal).as_bytes(), sender);
+            return;
+        }
+        if words.get(1) != Some(&"spectator") && self.remote.is_none() {
+            eprintln!("Player two joined from {}", sender);
+            self.remote = Some(sender);
+            self.connected = true;
+            self.last_heard = Instant::now();
+        }
+        let _ = self.socket.send_to(format!("WELCOME {}", self.get_cookie(sender)).as_bytes(), sender);
+    }
+
+    // A spectator only starts being streamed to once it has echoed its cookie
+    fn on_want(&mut self, words: &[&str], sender: SocketAddr) {
+        if words.get(2).and_then(|cookie| cookie.parse::<u64>().ok()) != Some(self.get_cookie(sender)) {
+            return;
+        }
+        let wanted_tick = match words.get(1).and_then(|tick| tick.parse::<u32>().ok()) {
+            Some(tick) if tick <= self.last_run_tick.saturating_add(1) => tick,
+            _ => return
+        };
+        if wanted_tick < self.get_oldest_kept_tick() {
+            let _ = self.socket.send_to(b"REFUSED too far behind", sender);
+            return;
+        }
+        match self.spectators.iter().position(|&(spectator, _, _)| spectator == sender) {
+            Some(index) => self.spectators[index] = (sender, wanted_tick, Instant::now()),
+            None if self.spectators.len() < MAX_SPECTATORS => {
+                eprintln!("Spectator joined from {}", sender);
+                self.spectators.push((sender, wanted_tick, Instant::now()));
+            },
+            None => {
+                let _ = self.socket.send_to(b"REFUSED too many spectators", sender);
+            }
+        }
+    }
+
+    fn get_cookie(&self, address: SocketAddr) -> u64 {
+        return self.cookie_keys.hash_one(address);
+    }
+
+    // The first tick the host still has the inputs of, for spectators
+    fn get_oldest_kept_tick(&self) -> u32 {
+        self.last_run_tick.saturating_sub(MAX_SPECTATOR_LAG_TICKS).max(1)
+    }
+
+    // Ticks that haven't run yet and aren't too far ahead to be real
+    fn is_in_window(&self, tick: u32) -> bool {
+        tick > self.last_run_tick && tick - self.last_run_tick <= MAX_TICKS_AHEAD
+    }
+
+    /// True when this end's input for `tick` still has to be picked, it is used INPUT_DELAY_TICKS later
+    pub fn needs_local_input(&self, tick: u32) -> bool {
+        self.role != Role::SPECTATOR && self.connected && tick + INPUT_DELAY_TICKS > self.last_queued_tick
+    }
+
+    pub fn queue_local_input(&mut self, tick: u32, action: MoveDirection) {
+        self.last_queued_tick = tick + INPUT_DELAY_TICKS;
+        self.local_inputs.insert(self.last_queued_tick, action);
+    }
+
+    /// Both players' inputs for the tick, player one first, or None if the tick has to wait for the network
+    pub fn get_inputs(&self, tick: u32) -> Option<[MoveDirection; 2]> {
+        if self.desynced || self.disconnected || !self.connected {
+            return None;
+        }
+        if self.role == Role::SPECTATOR {
+            return self.confirmed_inputs.get(&tick).cloned();
+        }
+        // Nobody could have pressed anything in time for the first few ticks
+        if tick <= INPUT_DELAY_TICKS {
+            return Some([MoveDirection::NONE, MoveDirection::NONE]);
+        }
+
+        let local = self.local_inputs.get(&tick)?.clone();
+        let remote = self.remote_inputs.get(&tick)?.clone();
+        if self.role == Role::HOST { Some([local, remote]) } else { Some([remote, local]) }
+    }
+
+    /// Records a tick that has run, hashing the game's state every HASH_INTERVAL_TICKS
+    pub fn on_tick(&mut self, tick: u32, inputs: [MoveDirection; 2], game: &Game) {
+        self.last_run_tick = tick;
+        if self.role == Role::HOST {
+            self.confirmed_inputs.insert(tick, inputs);
+        }
+        if tick.is_multiple_of(HASH_INTERVAL_TICKS) {
+            self.local_hashes.insert(tick, get_state_hash(game));
+            self.check_hashes();
+        }
+
+        // Old ticks can't be asked for again
+        if tick > RESEND_TICKS * 2 {
+            let oldest = tick - RESEND_TICKS * 2;
+            self.local_inputs.retain(|&input_tick, _| input_tick >= oldest);
+            self.remote_inputs.retain(|&input_tick, _| input_tick >= oldest);
+            // The remote keeps resending its latest hash, so one that came in after a match would stay forever.
+            // A local hash the remote never answered would stay too.
+            self.remote_hashes.retain(|&hash_tick, _| hash_tick >= oldest);
+            self.local_hashes.retain(|&hash_tick, _| hash_tick >= oldest);
+            if self.role != Role::HOST {
+                self.confirmed_inputs.retain(|&input_tick, _| input_tick >= oldest);
+            }
+        }
+        // The host keeps ticks for spectators that fall behind or join late. Ticks run one at a time, so only the
+        // one that just went out of reach has to go.
+        if self.role == Role::HOST && tick > MAX_SPECTATOR_LAG_TICKS {
+            self.confirmed_inputs.remove(&(tick - MAX_SPECTATOR_LAG_TICKS - 1));
+        }
+    }
+
+    fn check_hashes(&mut self) {
+        let mut checked = vec![];
+        for (tick, remote_hash) in self.remote_hashes.iter() {
+            if let Some(local_hash) = self.local_hashes.get(tick) {
+                if local_hash != remote_hash && !self.desynced {
+                    eprintln!("Desync at tick {}: local state {:016x}, remote state {:016x}", tick, local_hash, remote_hash);
+                    self.desynced = true;
+                }
+                checked.push(*tick);
+            }
+        }
+        for tick in checked {
+            self.remote_hashes.remove(&tick);
+            self.local_hashes.remove(&tick);
+        }
+    }
+
+    /// Sends this frame's packets, every recent input again in case earlier ones were lost
+    pub fn send(&mut self) {
+        let remote = match self.remote {
+            Some(remote) => remote,
+            None => return
+        };
+        if self.refused.is_some() {
+            return;
+        }
+        if self.role == Role::SPECTATOR {
+            let message = match self.cookie {
+                Some(cookie) => format!("WANT {} {}", self.last_run_tick.saturating_add(1), cookie),
+                None => format!("HELLO spectator {} {}", self.seed, self.map_hash)
+            };
+            let _ = self.socket.send_to(message.as_bytes(), remote);
+            return;
+        }
+        if !self.connected {
+            let hello = format!("HELLO player {} {}", self.seed, self.map_hash);
+            let _ = self.socket.send_to(hello.as_bytes(), remote);
+            return;
+        }
+
+        let first_tick = self.last_queued_tick.saturating_sub(RESEND_TICKS - 1).max(INPUT_DELAY_TICKS + 1);
+        let mut inputs = format!("INPUTS {}", first_tick);
+        for tick in first_tick..self.last_queued_tick + 1 {
+            let action = self.local_inputs.get(&tick).cloned().unwrap_or(MoveDirection::NONE);
+            inputs.push_str(&format!(" {}", action_name(&action)));
+        }
+        let _ = self.socket.send_to(inputs.as_bytes(), remote);
+
+        let latest_hash = self.local_hashes.iter().max_by_key(|&(tick, _)| *tick).map(|(tick, hash)| (*tick, *hash));
+        let hash = latest_hash.map(|(tick, hash)| format!("HASH {} {}", tick, hash));
+        if let Some(ref hash) = hash {
+            let _ = self.socket.send_to(hash.as_bytes(), remote);
+        }
+
+        if self.role == Role::HOST {
+            for &(spectator, wanted_tick, _) in self.spectators.iter() {
+                // Sent even when empty, it tells the spectator it is connected
+                let mut ticks = format!("TICKS {}", wanted_tick);
+                let last_tick = self.last_run_tick.min(wanted_tick.saturating_add(RESEND_TICKS - 1));
+                for tick in wanted_tick..last_tick.saturating_add(1) {
+                    if let Some(actions) = self.confirmed_inputs.get(&tick) {
+                        ticks.push_str(&format!(" {},{}", action_name(&actions[0]), action_name(&actions[1])));
+                    }
+                }
+                let _ = self.socket.send_to(ticks.as_bytes(), spectator);
+                if let Some(ref hash) = hash {
+                    let _ = self.socket.send_to(hash.as_bytes(), spectator);
+                }
+            }
+        }
+    }
+
+    /// What to show over the game while it can't run, if anything
+    pub fn get_status(&self) -> Option<String> {
+        if let Some(ref refused) = self.refused {
+            Some(format!("REFUSED: {}", refused.to_uppercase()))
+        } else if self.desynced {
+            Some(format!("DESYNC AFTER TICK {}", self.last_run_tick))
+        } else if self.disconnected {
+            Some(format!("CONNECTION LOST AFTER TICK {}", self.last_run_tick))
+        } else if !self.connected {
+            Some(match self.role {
+                Role::HOST => format!("WAITING FOR PLAYER TWO ON PORT {}",
+                                      self.socket.local_addr().map(|address| address.port()).unwrap_or(0)),
+                _ => "CONNECTING".to_string()
+            })
+        } else {
+            None
+        }
+    }
+}
+
+/// FNV-1a over the save state, which holds everything the simulation depends on
+pub fn get_state_hash(game: &Game) -> u64 {
+    return fnv_hash(game.get_save_state().to_text().bytes());
+}
+
+/// FNV-1a over the map's size and pixels, for checking both ends loaded the same map
+pub fn get_map_hash(map_image: &Image) -> u64 {
+    let size = map_image.get_size();
+    let mut bytes = vec![];
+    bytes.extend_from_slice(&size.x.to_le_bytes());
+    bytes.extend_from_slice(&size.y.to_le_bytes());
+    for y in 0..size.y {
+        for x in 0..size.x {
+            let pixel = map_image.get_pixel(x, y);
+            bytes.extend_from_slice(&[pixel.red, pixel.green, pixel.blue, pixel.alpha]);
+        }
+    }
+    return fnv_hash(bytes.into_iter());
+}
+
+fn fnv_hash<I: Iterator<Item = u8>>(bytes: I) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    return hash;
+}
+
+fn action_name(action: &MoveDirection) -> String {
+    format!("{:?}", action).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support;
+
+    const SEED: u64 = 7;
+    const MAP_HASH: u64 = 1234;
+
+    fn address(port: u16) -> SocketAddr {
+        SocketAddr::from(([127, 0, 0, 1], port))
+    }
+
+    fn host() -> NetSession {
+        NetSession::host(0, SEED, MAP_HASH).expect("a free port to host on")
+    }
+
+    #[test]
+    fn refuses_players_on_a_different_seed_or_map() {
+        let mut session = host();
+        session.on_message(&format!("HELLO player {} {}", SEED + 1, MAP_HASH), address(9000));
+        session.on_message(&format!("HELLO player {} {}", SEED, MAP_HASH + 1), address(9001));
+        assert_eq!(session.remote, None);
+
+        session.on_message(&format!("HELLO player {} {}", SEED, MAP_HASH), address(9002));
+        assert_eq!(session.remote, Some(address(9002)));
+    }
+
+    #[test]
+    fn spectators_must_echo_their_cookie() {
+        let mut session = host();
+        session.on_message(&format!("HELLO spectator {} {}", SEED, MAP_HASH), address(9000));
+        assert!(session.spectators.is_empty());
+
+        session.on_message("WANT 1 0", address(9000));
+        assert!(session.spectators.is_empty());
+
+        let cookie = session.get_cookie(address(9000));
+        session.on_message(&format!("WANT 1 {}", cookie), address(9000));
+        assert_eq!(session.spectators.len(), 1);
+        assert_eq!((session.spectators[0].0, session.spectators[0].1), (address(9000), 1));
+    }
+
+    #[test]
+    fn spectators_are_capped() {
+        let mut session = host();
+        for port in 0..MAX_SPECTATORS as u16 + 2 {
+            let cookie = session.get_cookie(address(9000 + port));
+            session.on_message(&format!("WANT 1 {}", cookie), address(9000 + port));
+        }
+        assert_eq!(session.spectators.len(), MAX_SPECTATORS);
+    }
+
+    #[test]
+    fn ignores_ticks_outside_the_window() {
+        let mut session = NetSession::join("127.0.0.1:9000", Role::GUEST, SEED, MAP_HASH).expect("a local socket");
+        session.on_message(&format!("INPUTS {} up up", u32::MAX), address(9000));
+        session.on_message(&format!("INPUTS {} up", MAX_TICKS_AHEAD + 1), address(9000));
+        session.on_message("INPUTS 0 up", address(9000));
+        assert!(session.remote_inputs.is_empty());
+
+        session.on_message("INPUTS 4 up", address(9000));
+        assert_eq!(session.remote_inputs.get(&4), Some(&MoveDirection::UP));
+    }
+
+    #[test]
+    fn wants_past_the_last_tick_are_ignored() {
+        let mut session = host();
+        let cookie = session.get_cookie(address(9000));
+        session.on_message(&format!("WANT {} {}", u32::MAX, cookie), address(9000));
+        assert!(session.spectators.is_empty());
+    }
+
+    #[test]
+    fn the_host_only_keeps_ticks_a_spectator_may_still_ask_for() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["#####",
+                                              "#P..#",
+                                              "#####"]);
+        let game = Game::new(&image, &sprite_sheet, SEED);
+        let mut session = host();
+        let last_tick = MAX_SPECTATOR_LAG_TICKS + 10;
+        for tick in 1..last_tick + 1 {
+            session.on_tick(tick, [MoveDirection::NONE, MoveDirection::NONE], &game);
+        }
+        assert_eq!(session.confirmed_inputs.len(), MAX_SPECTATOR_LAG_TICKS as usize + 1);
+
+        let cookie = session.get_cookie(address(9000));
+        session.on_message(&format!("WANT 9 {}", cookie), address(9000));
+        assert!(session.spectators.is_empty());
+        session.on_message(&format!("WANT 10 {}", cookie), address(9000));
+        assert_eq!(session.spectators.len(), 1);
+    }
+
+    #[test]
+    fn quiet_spectators_are_dropped() {
+        let mut session = host();
+        let cookie = session.get_cookie(address(9000));
+        session.on_message(&format!("WANT 1 {}", cookie), address(9000));
+
+        session.check_timeouts(Instant::now() + DISCONNECT_TIMEOUT / 2);
+        assert_eq!(session.spectators.len(), 1);
+        session.check_timeouts(Instant::now() + DISCONNECT_TIMEOUT * 2);
+        assert!(session.spectators.is_empty());
+    }
+
+    #[test]
+    fn a_quiet_host_stops_the_game() {
+        let mut session = NetSession::join("127.0.0.1:9000", Role::GUEST, SEED, MAP_HASH).expect("a local socket");
+        session.on_message("WELCOME 5", address(9000));
+        assert!(session.get_inputs(1).is_some());
+
+        session.check_timeouts(Instant::now() + DISCONNECT_TIMEOUT * 2);
+        assert!(session.disconnected);
+        assert!(session.get_inputs(1).is_none());
+        assert!(session.get_status().is_some());
+    }
+}
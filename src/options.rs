@@ -0,0 +1,326 @@
+use std::str::FromStr;
+
+use error::GameError;
+use score;
+use party::Party;
+use player;
+
+pub const USAGE: &'static str = "Usage: rust-man [options]
+
+Game:
+    --map <path>              Map image to play, default res/game_map.png
+    --level <n>               Level to start on, default 1. Ghosts stay frightened for less time on later levels
+    --lives <n>               Lives to start with, default 3
+    --seed <n>                Seed for the game's random numbers, default 0
+    --pre-turn <pixels>       Arcade pixels before a tile centre Pac-Man can turn, cutting the corner, default 4
+    --post-turn <pixels>      Arcade pixels past a tile centre Pac-Man can still turn, default 3
+    --two-player              Two players take turns, swapping after each death
+    --separate-controls       Player two steers with the arrow keys instead of sharing WASD
+    --coop                    A second player joins as another Pac-Man, on the arrow keys
+    --versus                  A second player joins as a ghost hunting Pac-Man, on the arrow keys
+    --gamepad                 The second player uses the first gamepad instead of the arrow keys
+    --load-state <path>       Start from a save state, F5 and F9 then save and load it too
+    --script <path>           Rules script for a custom game mode, see script.rs for its hooks, not over the network
+
+Network:
+    --host <port>             Host a versus game over UDP, playing Pac-Man
+    --join <address>          Join a hosted versus game as the ghost, e.g. 192.168.1.2:7777
+    --spectate <address>      Watch a hosted game without playing
+
+Display:
+    --scale <n>               Window size as a multiple of the arcade resolution, default 4
+    --fullscreen              Start in fullscreen
+    --frame-cap <fps>         Limit the frame rate, 0 for no limit
+    --vsync                   Wait for the monitor's refresh, overrides --frame-cap
+
+Replays:
+    --record <path>           Record the inputs of this game to a replay file
+    --playback <path>         Play back a recorded replay instead of taking input
+    --headless                Run without a window, the bot playing until its lives or --frames run out
+    --frames <n>              Most fixed updates each headless game runs for, default 36000
+    --games <n>               Headless games to play one after another, each on the next seed, default 1.
+                              Exits with 1 if a replay ends on a different score, 2 if --frames ran out first
+
+Tools:
+    --validate-map <path>     Check a map image for problems, exits with 0 if it is valid
+    --render-frame <n>        Render the frame after n fixed updates to --out, optionally checking it against --golden
+    --out <path>              Where --render-frame writes its image, default frame.png
+    --golden <path>           Image the rendered frame must match
+    --pack-assets <path>      Pack the resources into an archive
+    --env                     Drive the game over stdin and stdout, see env.rs
+    --dev                     Reload the map and sprite sheet when they are saved, - and = halve and double the tile size
+    --help                    Show this message";
+
+// Ten minutes of fixed updates
+const DEFAULT_HEADLESS_FRAMES: u32 = 36000;
+
+/// Everything that can be set from the command line
+pub struct Options {
+    pub map_path: String,
+    pub level: u32,
+    pub lives: u32,
+    pub seed: u64,
+    /// Pac-Man's cornering windows, in tiles
+    pub pre_turn: f32,
+    pub post_turn: f32,
+    pub two_player: bool,
+    pub separate_controls: bool,
+    pub party: Option<Party>,
+    pub gamepad: bool,
+    /// Where F5 saves and F9 loads
+    pub state_path: String,
+    pub load_state: bool,
+    pub script_path: Option<String>,
+    pub net_host: Option<u16>,
+    pub net_join: Option<String>,
+    pub spectate: Option<String>,
+
+    pub scale: u32,
+    pub fullscreen: bool,
+    pub frame_cap: u32,
+    pub vsync: bool,
+
+    pub record_path: Option<String>,
+    pub playback_path: Option<String>,
+    pub headless: bool,
+    pub frames: u32,
+    pub games: u32,
+
+    pub validate_map_path: Option<String>,
+    pub render_frame: Option<u32>,
+    pub out_path: String,
+    pub golden_path: Option<String>,
+    pub pack_assets_path: Option<String>,
+    pub env: bool,
+    pub dev: bool,
+    pub help: bool
+}
+
+impl Options {
+    pub fn new() -> Options {
+        Options {
+            map_path: super::MAP_PATH.to_string(),
+            level: 1,
+            lives: score::STARTING_LIVES,
+            seed: 0,
+            pre_turn: player::PRE_TURN,
+            post_turn: player::POST_TURN,
+            two_player: false,
+            separate_controls: false,
+            party: None,
+            gamepad: false,
+            state_path: super::QUICK_SAVE_PATH.to_string(),
+            load_state: false,
+            script_path: None,
+            net_host: None,
+            net_join: None,
+            spectate: None,
+
+            scale: super::SCREEN_SCALE,
+            fullscreen: false,
+            frame_cap: 0,
+            vsync: false,
+
+            record_path: None,
+            playback_path: None,
+            headless: false,
+            frames: DEFAULT_HEADLESS_FRAMES,
+            games: 1,
+
+            validate_map_path: None,
+            render_frame: None,
+            out_path: "frame.png".to_string(),
+            golden_path: None,
+            pack_assets_path: None,
+            env: false,
+            dev: false,
+            help: false
+        }
+    }
+
+    /// Parses the arguments after the program name
+    pub fn parse(args: &[String]) -> Result<Options, GameError> {
+        let mut options = Options::new();
+        let mut args = args.iter();
+
+        while let Some(arg) = args.next() {
+            let name = arg.as_str();
+            match name {
+                "--map" => options.map_path = next_value(name, &mut args)?,
+                "--level" => options.level = parse_value(name, &mut args)?,
+                "--lives" => options.lives = parse_value(name, &mut args)?,
+                "--seed" => options.seed = parse_value(name, &mut args)?,
+                "--pre-turn" => options.pre_turn = parse_value::<f32, _>(name, &mut args)? / super::NATIVE_TILE_SIZE as f32,
+                "--post-turn" => options.post_turn = parse_value::<f32, _>(name, &mut args)? / super::NATIVE_TILE_SIZE as f32,
+                "--two-player" => options.two_player = true,
+                "--separate-controls" => options.separate_controls = true,
+                "--coop" => options.party = Some(Party::COOP),
+                "--versus" => options.party = Some(Party::VERSUS),
+                "--gamepad" => options.gamepad = true,
+                "--load-state" => {
+                    options.state_path = next_value(name, &mut args)?;
+                    options.load_state = true;
+                },
+                "--script" => options.script_path = Some(next_value(name, &mut args)?),
+                "--host" => options.net_host = Some(parse_value(name, &mut args)?),
+                "--join" => options.net_join = Some(next_value(name, &mut args)?),
+                "--spectate" => options.spectate = Some(next_value(name, &mut args)?),
+                "--scale" => options.scale = parse_value(name, &mut args)?,
+                "--fullscreen" => options.fullscreen = true,
+                "--frame-cap" => options.frame_cap = parse_value(name, &mut args)?,
+                "--vsync" => options.vsync = true,
+                "--record" => options.record_path = Some(next_value(name, &mut args)?),
+                "--playback" => options.playback_path = Some(next_value(name, &mut args)?),
+                "--headless" => options.headless = true,
+                "--frames" => options.frames = parse_value(name, &mut args)?,
+                "--games" => options.games = parse_value(name, &mut args)?,
+                "--validate-map" => options.validate_map_path = Some(next_value(name, &mut args)?),
+                "--render-frame" => options.render_frame = Some(parse_value(name, &mut args)?),
+                "--out" => options.out_path = next_value(name, &mut args)?,
+                "--golden" => options.golden_path = Some(next_value(name, &mut args)?),
+                "--pack-assets" => options.pack_assets_path = Some(next_value(name, &mut args)?),
+                "--env" => options.env = true,
+                "--dev" => options.dev = true,
+                "--help" | "-h" => options.help = true,
+                _ => return Err(GameError::UnknownArgument { name: name.to_string() })
+            }
+        }
+
+        if options.level == 0 {
+            return Err(GameError::InvalidArgument { name: "--level".to_string(), value: "0".to_string() });
+        }
+        // Past half a tile the window would reach into the neighbouring tile's
+        for &(name, tiles) in [("--pre-turn", options.pre_turn), ("--post-turn", options.post_turn)].iter() {
+            if !(0.0..=0.5).contains(&tiles) {
+                return Err(GameError::InvalidArgument { name: name.to_string(), value: (tiles * super::NATIVE_TILE_SIZE as f32).to_string() });
+            }
+        }
+        if options.scale == 0 {
+            return Err(GameError::InvalidArgument { name: "--scale".to_string(), value: "0".to_string() });
+        }
+        if options.games == 0 {
+            return Err(GameError::InvalidArgument { name: "--games".to_string(), value: "0".to_string() });
+        }
+        // A replay holds a single game
+        if options.games > 1 && (options.record_path.is_some() || options.playback_path.is_some()) {
+            return Err(GameError::InvalidArgument { name: "--games".to_string(), value: "can't be used with replays".to_string() });
+        }
+        if options.record_path.is_some() && options.playback_path.is_some() {
+            return Err(GameError::InvalidArgument { name: "--record".to_string(), value: "can't be used with --playback".to_string() });
+        }
+        if options.party.is_some() && options.two_player {
+            return Err(GameError::InvalidArgument { name: "--two-player".to_string(), value: "can't be used with --coop or --versus".to_string() });
+        }
+        // Replays only hold player one's inputs
+        if options.party.is_some() && (options.record_path.is_some() || options.playback_path.is_some()) {
+            return Err(GameError::InvalidArgument { name: "--record".to_string(), value: "can't be used with --coop or --versus".to_string() });
+        }
+        // Replays are played from the start of a level, not from a save state
+        if options.load_state && (options.record_path.is_some() || options.playback_path.is_some()) {
+            return Err(GameError::InvalidArgument { name: "--load-state".to_string(), value: "can't be used with replays".to_string() });
+        }
+        // Replays don't record which script made the rules
+        if options.script_path.is_some() && (options.record_path.is_some() || options.playback_path.is_some()) {
+            return Err(GameError::InvalidArgument { name: "--script".to_string(), value: "can't be used with replays".to_string() });
+        }
+        // Both ends of a network game have to start from the same state and keep running the same simulation
+        let network_modes = [options.net_host.is_some(), options.net_join.is_some(), options.spectate.is_some()];
+        if network_modes.iter().filter(|&&enabled| enabled).count() > 1 {
+            return Err(GameError::InvalidArgument { name: "--host".to_string(), value: "can't be used with --join or --spectate".to_string() });
+        }
+        if network_modes.contains(&true) {
+            if options.two_player || options.party == Some(Party::COOP) || options.load_state || options.headless || options.dev ||
+               options.record_path.is_some() || options.playback_path.is_some() {
+                return Err(GameError::InvalidArgument { name: "--host".to_string(),
+                                                        value: "can't be used with local modes, save states, replays, --headless or --dev".to_string() });
+            }
+            // Only this end would run the script's rules, the other would desync straight away
+            if options.script_path.is_some() {
+                return Err(GameError::InvalidArgument { name: "--script".to_string(), value: "can't be used in a network game".to_string() });
+            }
+            if options.pre_turn != player::PRE_TURN || options.post_turn != player::POST_TURN {
+                return Err(GameError::InvalidArgument { name: "--pre-turn".to_string(), value: "can't be changed in a network game".to_string() });
+            }
+            options.party = Some(Party::VERSUS);
+        }
+        return Ok(options);
+    }
+}
+
+fn next_value<'a, I: Iterator<Item = &'a String>>(name: &str, args: &mut I) -> Result<String, GameError> {
+    match args.next() {
+        Some(value) => Ok(value.clone()),
+        None => Err(GameError::InvalidArgument { name: name.to_string(), value: String::new() })
+    }
+}
+
+fn parse_value<'a, T: FromStr, I: Iterator<Item = &'a String>>(name: &str, args: &mut I) -> Result<T, GameError> {
+    let value = next_value(name, args)?;
+    value.parse::<T>().map_err(|_| GameError::InvalidArgument { name: name.to_string(), value: value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Options, GameError> {
+        Options::parse(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn scripts_are_refused_over_the_network() {
+        assert!(parse(&["--script", "mode.rhai", "--host", "7777"]).is_err());
+        assert!(parse(&["--script", "mode.rhai", "--join", "127.0.0.1:7777"]).is_err());
+        assert!(parse(&["--script", "mode.rhai"]).is_ok());
+    }
+
+    // The name of the argument an error blames, or None if parsing went through
+    fn refused_argument(args: &[&str]) -> Option<String> {
+        match parse(args) {
+            Err(GameError::InvalidArgument { name, .. }) => Some(name),
+            Err(GameError::UnknownArgument { name }) => Some(name),
+            _ => None
+        }
+    }
+
+    #[test]
+    fn values_are_read_after_their_names() {
+        let options = parse(&["--map", "maze.txt", "--level", "3", "--lives", "5", "--seed", "42", "--headless", "--frames", "600"]).unwrap();
+        assert_eq!(options.map_path, "maze.txt");
+        assert_eq!((options.level, options.lives, options.seed), (3, 5, 42));
+        assert!(options.headless);
+        assert_eq!(options.frames, 600);
+    }
+
+    #[test]
+    fn cornering_is_given_in_arcade_pixels() {
+        let options = parse(&["--pre-turn", "4", "--post-turn", "2"]).unwrap();
+        assert_eq!((options.pre_turn, options.post_turn), (0.5, 0.25));
+        assert_eq!(refused_argument(&["--pre-turn", "5"]), Some("--pre-turn".to_string()));
+    }
+
+    #[test]
+    fn bad_arguments_are_refused() {
+        assert_eq!(refused_argument(&["--fly"]), Some("--fly".to_string()));
+        assert_eq!(refused_argument(&["--seed"]), Some("--seed".to_string()));
+        assert_eq!(refused_argument(&["--seed", "many"]), Some("--seed".to_string()));
+        assert_eq!(refused_argument(&["--level", "0"]), Some("--level".to_string()));
+        assert_eq!(refused_argument(&["--scale", "0"]), Some("--scale".to_string()));
+    }
+
+    #[test]
+    fn clashing_modes_are_refused() {
+        assert_eq!(refused_argument(&["--games", "2", "--record", "run.replay"]), Some("--games".to_string()));
+        assert_eq!(refused_argument(&["--record", "a.replay", "--playback", "b.replay"]), Some("--record".to_string()));
+        assert_eq!(refused_argument(&["--two-player", "--coop"]), Some("--two-player".to_string()));
+        assert_eq!(refused_argument(&["--versus", "--playback", "run.replay"]), Some("--record".to_string()));
+        assert_eq!(refused_argument(&["--load-state", "save.txt", "--record", "run.replay"]), Some("--load-state".to_string()));
+        assert_eq!(refused_argument(&["--host", "7777", "--join", "127.0.0.1:7777"]), Some("--host".to_string()));
+        assert_eq!(refused_argument(&["--host", "7777", "--two-player"]), Some("--host".to_string()));
+    }
+
+    #[test]
+    fn network_games_are_versus() {
+        assert_eq!(parse(&["--join", "127.0.0.1:7777"]).unwrap().party, Some(Party::VERSUS));
+    }
+}
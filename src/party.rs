@@ -0,0 +1,202 @@
+use std::collections::VecDeque;
+
+use sfml::graphics::Color;
+
+use animation_sprite::AnimationSprite;
+use ecs::{World, Entity, Transform, Collider, PlayerControl, Ghost, GhostBrain};
+use grid_mover::{self, GridMover};
+use level::Level;
+use player::MoveDirection;
+use actor::Actor;
+
+// Tiles per millisecond, a little slower than Pac-Man like the arcade
+const GHOST_SPEED: f32 = 0.0059;
+const FRIGHTENED_GHOST_SPEED: f32 = 0.0031;
+
+/// Colours of the arcade's four ghosts in the order they spawn, the first is the one a versus player steers
+pub const GHOST_COLORS: [Color; 4] = [Color { red: 255, green: 0, blue: 0, alpha: 255 },
+                                      Color { red: 255, green: 184, blue: 255, alpha: 255 },
+                                      Color { red: 0, green: 255, blue: 255, alpha: 255 },
+                                      Color { red: 255, green: 184, blue: 82, alpha: 255 }];
+
+const DIRECTIONS: [MoveDirection; 4] = [MoveDirection::UP, MoveDirection::LEFT, MoveDirection::DOWN, MoveDirection::RIGHT];
+
+/// Modes for a second local player sharing the maze
+#[derive(Clone, PartialEq, Debug)]
+pub enum Party {
+    /// Two Pac-Men clearing the maze together
+    COOP,
+    /// The second player is a ghost hunting Pac-Man
+    VERSUS
+}
+
+/// Adds a ghost standing on `tile`, steered by the player in `slot`, or with None by a `GhostBrain` for `chase_system`
+pub fn spawn_ghost<'s>(world: &mut World<'s>, level: &Level, tile: (i32, i32), mut sprite: AnimationSprite<'s>,
+                       slot: Option<usize>, color: Color) -> Entity {
+    let local_bounds = sprite.sfml_sprite.get_local_bounds();
+    sprite.sfml_sprite.set_origin2f(local_bounds.width * 0.5, local_bounds.height * 0.5);
+    sprite.set_size(level.get_tile_size());
+    sprite.sfml_sprite.set_color(&color);
+
+    let ghost = world.spawn();
+    let mover = GridMover::new(tile, GHOST_SPEED);
+    world.transforms.insert(ghost, Transform { position: mover.get_world_position(level), rotation: 0.0 });
+    world.movers.insert(ghost, mover);
+    world.sprites.insert(ghost, sprite);
+    world.colliders.insert(ghost, Collider { size: 1.0 });
+    match slot {
+        Some(slot) => world.controls.insert(ghost, PlayerControl { action: MoveDirection::NONE, slot: slot }),
+        None => world.brains.insert(ghost, GhostBrain { target: tile })
+    }
+    world.ghosts.insert(ghost, Ghost { home: tile, color: color });
+    return ghost;
+}
+
+/// Turns ghosts blue and slows them down while they are frightened
+pub fn frighten_system(world: &mut World, frightened: bool) {
+    for (entity, ghost) in world.ghosts.iter() {
+        if let Some(mover) = world.movers.get_mut(entity) {
+            mover.speed = if frightened { FRIGHTENED_GHOST_SPEED } else { GHOST_SPEED };
+        }
+        if let Some(sprite) = world.sprites.get_mut(entity) {
+            sprite.sfml_sprite.set_color(&if frightened { Color::blue() } else { ghost.color });
+        }
+    }
+}
+
+/// Points every ghost brain at the nearest Pac-Man, or home while frightened
+pub fn target_system(world: &mut World, frightened: bool) {
+    let pacman_tiles: Vec<(i32, i32)> = world.actors().filter(|actor| world.is_pacman(actor.get_entity()))
+                                                       .map(|actor| actor.get_tile()).collect();
+    for (entity, brain) in world.brains.iter_mut() {
+        let (tile, home) = match (world.movers.get(entity), world.ghosts.get(entity)) {
+            (Some(mover), Some(ghost)) => (mover.tile, ghost.home),
+            _ => continue
+        };
+        let nearest = pacman_tiles.iter().min_by_key(|other| (other.0 - tile.0).abs() + (other.1 - tile.1).abs());
+        brain.target = match nearest {
+            Some(&other) if !frightened => other,
+            _ => home
+        };
+    }
+}
+
+/// Steers ghosts with a brain towards their target tiles. Like the arcade a ghost picks its way once per tile,
+/// taking the open direction whose next tile is closest to the target in a straight line and never turning back
+/// unless it's in a dead end.
+pub fn chase_system(world: &mut World, level: &Level) {
+    for (entity, brain) in world.brains.iter() {
+        let target = brain.target;
+        let mover = match world.movers.get_mut(entity) {
+            Some(mover) => mover,
+            None => continue
+        };
+        // Past the centre the turn for this tile has been taken already
+        let unit = grid_mover::get_unit_vector(&mover.direction);
+        if mover.offset.x * unit.x + mover.offset.y * unit.y > 0.0 {
+            continue;
+        }
+
+        let reverse = grid_mover::get_opposite(&mover.direction);
+        let mut best: Option<(i32, MoveDirection)> = None;
+        for direction in DIRECTIONS.iter() {
+            let (x, y) = grid_mover::get_neighbour(mover.tile, direction);
+            if *direction == reverse || !is_open(level, x, y) {
+                continue;
+            }
+            let distance = (x - target.0).pow(2) + (y - target.1).pow(2);
+            // Ties go to the first direction, in the arcade's order
+            if best.as_ref().map(|&(best_distance, _)| distance < best_distance).unwrap_or(true) {
+                best = Some((distance, direction.clone()));
+            }
+        }
+        match best {
+            Some((_, direction)) => mover.request_direction(direction),
+            None => if mover.is_at_centre() {
+                mover.request_direction(reverse);
+            }
+        }
+    }
+}
+
+fn is_open(level: &Level, x: i32, y: i32) -> bool {
+    x >= 0 && y >= 0 && x < level.size.x as i32 && y < level.size.y as i32 && level.is_walkable(x, y)
+}
+
+/// The `count` open tiles furthest from `start` by walking, furthest first, so ghosts don't spawn on top of Pac-Man.
+/// Fewer come back if the maze doesn't have that many.
+pub fn get_farthest_tiles(level: &Level, start: (i32, i32), count: usize) -> Vec<(i32, i32)> {
+    let width = level.size.x as i32;
+    let height = level.size.y as i32;
+    let mut visited = vec![false; (width * height) as usize];
+    let mut queue = VecDeque::new();
+    // Every tile reached, nearest first
+    let mut reached = vec![];
+
+    if start.0 >= 0 && start.1 >= 0 && start.0 < width && start.1 < height {
+        visited[(start.1 * width + start.0) as usize] = true;
+        queue.push_back(start);
+    }
+    while let Some(tile) = queue.pop_front() {
+        if tile != start {
+            reached.push(tile);
+        }
+        for direction in DIRECTIONS.iter() {
+            let (x, y) = grid_mover::get_neighbour(tile, direction);
+            if !is_open(level, x, y) {
+                continue;
+            }
+            if !visited[(y * width + x) as usize] {
+                visited[(y * width + x) as usize] = true;
+                queue.push_back((x, y));
+            }
+        }
+    }
+    return reached.into_iter().rev().take(count).collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sfml::system::Vector2f;
+
+    fn add_mover(world: &mut World, tile: (i32, i32)) -> Entity {
+        let entity = world.spawn();
+        world.transforms.insert(entity, Transform { position: Vector2f::new(0.0, 0.0), rotation: 0.0 });
+        world.movers.insert(entity, GridMover::new(tile, GHOST_SPEED));
+        return entity;
+    }
+
+    fn add_ghost(world: &mut World, tile: (i32, i32), home: (i32, i32)) -> Entity {
+        let ghost = add_mover(world, tile);
+        world.ghosts.insert(ghost, Ghost { home: home, color: Color::red() });
+        world.brains.insert(ghost, GhostBrain { target: tile });
+        return ghost;
+    }
+
+    fn add_pacman(world: &mut World, tile: (i32, i32), slot: usize) {
+        let pacman = add_mover(world, tile);
+        world.controls.insert(pacman, PlayerControl { action: MoveDirection::NONE, slot: slot });
+    }
+
+    #[test]
+    fn brains_target_the_nearest_pacman() {
+        let mut world = World::new();
+        add_pacman(&mut world, (1, 1), 0);
+        add_pacman(&mut world, (9, 9), 1);
+        let ghost = add_ghost(&mut world, (8, 7), (5, 5));
+
+        target_system(&mut world, false);
+        assert_eq!(world.brains.get(ghost).unwrap().target, (9, 9));
+    }
+
+    #[test]
+    fn frightened_brains_head_home() {
+        let mut world = World::new();
+        add_pacman(&mut world, (1, 1), 0);
+        let ghost = add_ghost(&mut world, (2, 1), (5, 5));
+
+        target_system(&mut world, true);
+        assert_eq!(world.brains.get(ghost).unwrap().target, (5, 5));
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::PathBuf;
+
+use error::GameError;
+use player::{self, MoveDirection};
+use score;
+
+const REPLAY_HEADER: &'static str = "RUSTMAN-REPLAY 1";
+
+/// Settings a replay has to start from to play out the same way
+#[derive(Clone)]
+pub struct ReplayStart {
+    pub seed: u64,
+    pub level: u32,
+    pub lives: u32,
+    /// Pac-Man's cornering windows, in tiles
+    pub pre_turn: f32,
+    pub post_turn: f32
+}
+
+/// Writes the action taken on every fixed update that changed direction.
+/// The format is the header line, `seed`, `level`, `lives`, `pre_turn` and `post_turn` lines, then `<tick> <direction>` lines,
+/// then an `end <tick> <points>` line with the last tick that ran and the score it finished on.
+pub struct ReplayRecorder {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    failed: bool
+}
+
+impl ReplayRecorder {
+    pub fn create(path: &str, start: &ReplayStart) -> Result<ReplayRecorder, GameError> {
+        let path = PathBuf::from(path);
+        let file = File::create(&path).map_err(|_| GameError::InvalidResource { path: path.clone() })?;
+
+        let mut recorder = ReplayRecorder {
+            path: path,
+            writer: BufWriter::new(file),
+            failed: false
+        };
+        let header = format!("{}\nseed {}\nlevel {}\nlives {}\npre_turn {}\npost_turn {}", REPLAY_HEADER,
+                             start.seed, start.level, start.lives, start.pre_turn, start.post_turn);
+        recorder.write_line(&header);
+        Ok(recorder)
+    }
+
+    pub fn record(&mut self, tick: u32, action: &MoveDirection) {
+        if *action != MoveDirection::NONE {
+            let line = format!("{} {}", tick, format!("{:?}", action).to_lowercase());
+            self.write_line(&line);
+        }
+    }
+
+    /// Writes the end line, call once the game stops
+    pub fn finish(mut self, tick: u32, points: u32) -> Result<(), GameError> {
+        let line = format!("end {} {}", tick, points);
+        self.write_line(&line);
+        let path = self.path.clone();
+        self.writer.flush().map_err(|error| GameError::Io { path: path, error: error })
+    }
+
+    fn write_line(&mut self, line: &str) {
+        if self.failed {
+            return;
+        }
+        if writeln!(self.writer, "{}", line).is_err() {
+            // Only complain once rather than every frame
+            eprintln!("Could not write to replay {}, the rest of the game won't be recorded", self.path.display());
+            self.failed = true;
+        }
+    }
+}
+
+pub struct Replay {
+    pub start: ReplayStart,
+    /// The last tick the recording ran and the points it had then, None for a recording that never finished
+    pub end: Option<(u32, u32)>,
+    actions: VecDeque<(u32, MoveDirection)>
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Replay, GameError> {
+        let invalid = || GameError::InvalidResource { path: PathBuf::from(path) };
+        let read_failed = |error| GameError::Io { path: PathBuf::from(path), error: error };
+        let file = File::open(path).map_err(read_failed)?;
+        let mut lines = BufReader::new(file).lines();
+
+        let header = match lines.next() {
+            Some(header) => header.map_err(read_failed)?,
+            None => return Err(invalid())
+        };
+        if header != REPLAY_HEADER {
+            return Err(invalid());
+        }
+
+        let mut start = ReplayStart { seed: 0, level: 1, lives: score::STARTING_LIVES,
+                                      pre_turn: player::PRE_TURN, post_turn: player::POST_TURN };
+        let mut end = None;
+        let mut actions = VecDeque::new();
+        for line in lines {
+            let line = line.map_err(read_failed)?;
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next()) {
+                (Some("seed"), Some(seed)) => start.seed = seed.parse().map_err(|_| invalid())?,
+                (Some("level"), Some(level)) => start.level = level.parse().map_err(|_| invalid())?,
+                (Some("lives"), Some(lives)) => start.lives = lives.parse().map_err(|_| invalid())?,
+                (Some("pre_turn"), Some(pre_turn)) => start.pre_turn = pre_turn.parse().map_err(|_| invalid())?,
+                (Some("post_turn"), Some(post_turn)) => start.post_turn = post_turn.parse().map_err(|_| invalid())?,
+                (Some("end"), Some(tick)) => {
+                    let tick = tick.parse::<u32>().map_err(|_| invalid())?;
+                    let points = words.next().and_then(|points| points.parse::<u32>().ok()).ok_or(invalid())?;
+                    end = Some((tick, points));
+                },
+                (Some(tick), Some(action)) => {
+                    let tick = tick.parse::<u32>().map_err(|_| invalid())?;
+                    let action = player::parse_action(action).ok_or(invalid())?;
+                    actions.push_back((tick, action));
+                },
+                (None, _) => { },
+                _ => return Err(invalid())
+            }
+        }
+
+        Ok(Replay {
+            start: start,
+            end: end,
+            actions: actions
+        })
+    }
+
+    /// The recorded action for this tick, NONE if the direction wasn't changed
+    pub fn get_action(&mut self, tick: u32) -> MoveDirection {
+        let mut action = MoveDirection::NONE;
+        while self.actions.front().map(|&(action_tick, _)| action_tick <= tick).unwrap_or(false) {
+            action = self.actions.pop_front().unwrap().1;
+        }
+        return action;
+    }
+
+    /// Whether `tick` is past the end of the recording, or past its last action if it never finished
+    pub fn is_finished(&self, tick: u32) -> bool {
+        match self.end {
+            Some((end_tick, _)) => tick > end_tick,
+            None => self.actions.is_empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::fs;
+
+    use super::*;
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn recordings_load_back_the_same() {
+        let path = temp_path("rustman-replay-round-trip.replay");
+        let start = ReplayStart { seed: 9, level: 2, lives: 4, pre_turn: 0.25, post_turn: 0.125 };
+        let mut recorder = ReplayRecorder::create(&path, &start).unwrap();
+        recorder.record(3, &MoveDirection::LEFT);
+        recorder.record(4, &MoveDirection::NONE);
+        recorder.record(10, &MoveDirection::UP);
+        recorder.finish(20, 150).unwrap();
+
+        let mut replay = Replay::load(&path).unwrap();
+        assert_eq!((replay.start.seed, replay.start.level, replay.start.lives), (9, 2, 4));
+        assert_eq!((replay.start.pre_turn, replay.start.post_turn), (0.25, 0.125));
+        assert_eq!(replay.end, Some((20, 150)));
+        assert!(replay.get_action(2) == MoveDirection::NONE);
+        assert!(replay.get_action(3) == MoveDirection::LEFT);
+        assert!(replay.get_action(9) == MoveDirection::NONE);
+        assert!(replay.get_action(10) == MoveDirection::UP);
+        assert!(!replay.is_finished(20));
+        assert!(replay.is_finished(21));
+    }
+
+    #[test]
+    fn files_that_are_not_replays_are_refused() {
+        let path = temp_path("rustman-replay-bad-header.replay");
+        fs::write(&path, "RUSTMAN-SAVE 2\nseed 1\n").unwrap();
+        match Replay::load(&path) {
+            Err(GameError::InvalidResource { .. }) => { },
+            _ => panic!("a save state loaded as a replay")
+        }
+    }
+}
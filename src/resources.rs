@@ -0,0 +1,305 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use sfml::graphics::{Image, Texture, Font, IntRect};
+
+use error::GameError;
+
+// Directory to look in before anywhere else
+const ASSETS_ENV_VAR: &'static str = "RUST_MAN_ASSETS";
+const ARCHIVE_NAME: &'static str = "assets.pak";
+const ARCHIVE_MAGIC: &'static [u8; 4] = b"RMPK";
+// How many parents of the executable's directory to search, so `cargo run` finds the repo's res folder
+const EXECUTABLE_PARENT_DEPTH: usize = 2;
+
+/// Packed resources, stored as the magic bytes then a count and (name, data) pairs with little endian lengths
+pub struct Archive {
+    path: PathBuf,
+    /// Shared so a font can keep the bytes it reads from alive
+    entries: HashMap<String, Rc<[u8]>>
+}
+
+impl Archive {
+    pub fn open(path: &Path) -> Result<Archive, GameError> {
+        let mut data = vec![];
+        match File::open(path).and_then(|mut file| file.read_to_end(&mut data)) {
+            Ok(_) => { },
+            Err(_) => return Err(GameError::InvalidResource { path: path.to_path_buf() })
+        }
+
+        match Archive::parse(&data) {
+            Some(entries) => Ok(Archive { path: path.to_path_buf(), entries: entries }),
+            None => Err(GameError::InvalidResource { path: path.to_path_buf() })
+        }
+    }
+
+    fn parse(data: &[u8]) -> Option<HashMap<String, Rc<[u8]>>> {
+        if data.len() < 8 || &data[0..4] != ARCHIVE_MAGIC {
+            return None;
+        }
+
+        let read_u32 = |offset: usize| -> Option<usize> {
+            data.get(offset..offset + 4).map(|bytes| {
+                (bytes[0] as usize) | (bytes[1] as usize) << 8 | (bytes[2] as usize) << 16 | (bytes[3] as usize) << 24
+            })
+        };
+
+        let count = read_u32(4)?;
+        let mut offset = 8;
+        let mut entries = HashMap::new();
+
+        for _ in 0..count {
+            let name_length = read_u32(offset)?;
+            let name = String::from_utf8(data.get(offset + 4..offset + 4 + name_length)?.to_vec()).ok()?;
+            offset += 4 + name_length;
+
+            let data_length = read_u32(offset)?;
+            let entry: Rc<[u8]> = Rc::from(data.get(offset + 4..offset + 4 + data_length)?);
+            offset += 4 + data_length;
+
+            entries.insert(name, entry);
+        }
+        return Some(entries);
+    }
+
+    /// Packs the given resource files, found through `assets`, into a new archive
+    pub fn write(path: &Path, assets: &Assets, resource_paths: &[&str]) -> Result<(), GameError> {
+        let mut out = vec![];
+        out.extend_from_slice(ARCHIVE_MAGIC);
+        push_u32(&mut out, resource_paths.len());
+
+        for resource_path in resource_paths.iter() {
+            let resolved = assets.resolve_file(resource_path)?;
+            let mut data = vec![];
+            if File::open(&resolved).and_then(|mut file| file.read_to_end(&mut data)).is_err() {
+                return Err(GameError::InvalidResource { path: resolved });
+            }
+
+            push_u32(&mut out, resource_path.len());
+            out.extend_from_slice(resource_path.as_bytes());
+            push_u32(&mut out, data.len());
+            out.extend_from_slice(&data);
+        }
+
+        let written: io::Result<()> = File::create(path).and_then(|mut file| file.write_all(&out));
+        written.map_err(|_| GameError::InvalidResource { path: path.to_path_buf() })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&[u8]> {
+        self.entries.get(name).map(|entry| &entry[..])
+    }
+
+    fn get_shared(&self, name: &str) -> Option<Rc<[u8]>> {
+        self.entries.get(name).cloned()
+    }
+}
+
+fn push_u32(out: &mut Vec<u8>, value: usize) {
+    let value = value as u32;
+    out.extend_from_slice(&[value as u8, (value >> 8) as u8, (value >> 16) as u8, (value >> 24) as u8]);
+}
+
+/// A font along with the bytes it was loaded from. SFML reads glyphs from those bytes for as long as the font lives.
+pub struct AssetFont {
+    // Declared first so it is dropped before the bytes it reads from
+    font: Font,
+    _data: Option<Rc<[u8]>>
+}
+
+impl Deref for AssetFont {
+    type Target = Font;
+
+    fn deref(&self) -> &Font {
+        &self.font
+    }
+}
+
+/// Finds resources on disk or in a packed archive and loads each texture and font only once
+pub struct Assets {
+    search_paths: Vec<PathBuf>,
+    archive: Option<Archive>,
+    textures: HashMap<String, Rc<Texture>>,
+    fonts: HashMap<String, Rc<AssetFont>>
+}
+
+impl Assets {
+    /// Searches the env var override, then the executable's directory and its parents, then the working directory,
+    /// using the first archive found in any of them
+    pub fn new() -> Result<Assets, GameError> {
+        let mut assets = Assets::new_loose();
+        assets.archive = match assets.search_paths.iter().map(|dir| dir.join(ARCHIVE_NAME)).find(|path| path.is_file()) {
+            Some(archive_path) => Some(Archive::open(&archive_path)?),
+            None => None
+        };
+        Ok(assets)
+    }
+
+    /// Only loose files, for packing them into a new archive whatever state the old one is in
+    pub fn new_loose() -> Assets {
+        let mut search_paths = vec![];
+
+        if let Some(dir) = env::var_os(ASSETS_ENV_VAR) {
+            search_paths.push(PathBuf::from(dir));
+        }
+        if let Some(exe_dir) = env::current_exe().ok().and_then(|exe| exe.parent().map(|dir| dir.to_path_buf())) {
+            for dir in exe_dir.ancestors().take(EXECUTABLE_PARENT_DEPTH + 1) {
+                search_paths.push(dir.to_path_buf());
+            }
+        }
+        if let Ok(dir) = env::current_dir() {
+            if !search_paths.contains(&dir) {
+                search_paths.push(dir);
+            }
+        }
+
+        Assets {
+            search_paths: search_paths,
+            archive: None,
+            textures: HashMap::new(),
+            fonts: HashMap::new()
+        }
+    }
+
+    /// Finds a loose file in the search paths, ignoring the archive
+    pub fn resolve_file(&self, path: &str) -> Result<PathBuf, GameError> {
+        match self.search_paths.iter().map(|dir| dir.join(path)).find(|candidate| candidate.is_file()) {
+            Some(resolved) => Ok(resolved),
+            None => Err(self.missing(path))
+        }
+    }
+
+    fn missing(&self, path: &str) -> GameError {
+        let mut searched: Vec<PathBuf> = self.search_paths.iter().map(|dir| dir.join(path)).collect();
+        if let Some(ref archive) = self.archive {
+            searched.push(PathBuf::from(format!("{} (in {})", path, archive.path.display())));
+        }
+        GameError::MissingResource { path: path.to_string(), searched: searched }
+    }
+
+    /// Loose files win over the archive so single assets can be overridden
+    fn load<T, F, M>(&self, path: &str, from_file: F, from_memory: M) -> Result<T, GameError>
+        where F: Fn(&str) -> Option<T>, M: Fn(&[u8]) -> Option<T> {
+
+        if let Ok(resolved) = self.resolve_file(path) {
+            return resolved.to_str().and_then(from_file)
+                           .ok_or(GameError::InvalidResource { path: resolved.clone() });
+        }
+        match self.archive.as_ref().and_then(|archive| archive.get(path)) {
+            Some(data) => from_memory(data).ok_or(GameError::InvalidResource { path: PathBuf::from(path) }),
+            None => Err(self.missing(path))
+        }
+    }
+
+    pub fn image(&self, path: &str) -> Result<Image, GameError> {
+        self.load(path, Image::new_from_file, Image::new_from_memory)
+    }
+
+    pub fn text(&self, path: &str) -> Result<String, GameError> {
+        let read_file = |resolved: &str| {
+            let mut text = String::new();
+            File::open(resolved).and_then(|mut file| file.read_to_string(&mut text)).ok().map(|_| text)
+        };
+        self.load(path, read_file, |data| String::from_utf8(data.to_vec()).ok())
+    }
+
+    pub fn texture(&mut self, path: &str) -> Result<Rc<Texture>, GameError> {
+        if let Some(texture) = self.textures.get(path) {
+            return Ok(texture.clone());
+        }
+        let whole_texture = IntRect::new(0, 0, 0, 0);
+        let texture = Rc::new(self.load(path, Texture::new_from_file, |data| Texture::new_from_memory(data, &whole_texture))?);
+        self.textures.insert(path.to_string(), texture.clone());
+        Ok(texture)
+    }
+
+    /// Drops the cached texture so the next request reads it from disk again
+    pub fn forget_texture(&mut self, path: &str) {
+        self.textures.remove(path);
+    }
+
+    pub fn font(&mut self, path: &str) -> Result<Rc<AssetFont>, GameError> {
+        if let Some(font) = self.fonts.get(path) {
+            return Ok(font.clone());
+        }
+        let from_file = |resolved: &str| Font::new_from_file(resolved).map(|font| AssetFont { font: font, _data: None });
+        let from_memory = |_: &[u8]| {
+            let data = self.archive.as_ref().and_then(|archive| archive.get_shared(path))?;
+            Font::new_from_memory(&data).map(move |font| AssetFont { font: font, _data: Some(data) })
+        };
+        let font = Rc::new(self.load(path, from_file, from_memory)?);
+        self.fonts.insert(path.to_string(), font.clone());
+        Ok(font)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAP: &'static str = "res/game_map.png";
+    const SPRITE_SHEET: &'static str = "res/sprites/game.png";
+
+    fn read(path: &str) -> Vec<u8> {
+        let mut data = vec![];
+        File::open(path).and_then(|mut file| file.read_to_end(&mut data)).unwrap();
+        return data;
+    }
+
+    // Assets that only look in an empty directory, so anything they find comes from `archive`
+    fn archive_only(archive: Archive) -> Assets {
+        let empty_dir = env::temp_dir().join("rustman-assets-empty");
+        std::fs::create_dir_all(&empty_dir).unwrap();
+        Assets { search_paths: vec![empty_dir], archive: Some(archive), textures: HashMap::new(), fonts: HashMap::new() }
+    }
+
+    #[test]
+    fn archives_hold_the_files_they_were_packed_from() {
+        let path = env::temp_dir().join("rustman-archive-test.pak");
+        Archive::write(&path, &Assets::new_loose(), &[MAP, SPRITE_SHEET]).unwrap();
+
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(archive.get(MAP), Some(&read(MAP)[..]));
+        assert_eq!(archive.get(SPRITE_SHEET), Some(&read(SPRITE_SHEET)[..]));
+        assert_eq!(archive.get("res/missing.png"), None);
+    }
+
+    #[test]
+    fn damaged_archives_are_refused() {
+        let mut data = vec![];
+        data.extend_from_slice(ARCHIVE_MAGIC);
+        push_u32(&mut data, 1);
+        push_u32(&mut data, 100);
+        assert!(Archive::parse(&data).is_none());
+        assert!(Archive::parse(b"NOPE\0\0\0\0").is_none());
+    }
+
+    #[test]
+    fn missing_files_fall_back_to_the_archive() {
+        let path = env::temp_dir().join("rustman-archive-fallback-test.pak");
+        Archive::write(&path, &Assets::new_loose(), &[MAP]).unwrap();
+        let assets = archive_only(Archive::open(&path).unwrap());
+
+        let image = assets.image(MAP).unwrap();
+        assert_eq!(image.get_size(), Assets::new_loose().image(MAP).unwrap().get_size());
+        match assets.text("res/missing.txt") {
+            Err(GameError::MissingResource { ref searched, .. }) => assert_eq!(searched.len(), 2),
+            _ => panic!("a file in neither place was found")
+        }
+    }
+
+    #[test]
+    fn textures_are_only_loaded_once() {
+        let mut assets = Assets::new_loose();
+        let first = assets.texture(SPRITE_SHEET).unwrap();
+        let second = assets.texture(SPRITE_SHEET).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+
+        assets.forget_texture(SPRITE_SHEET);
+        assert!(!Rc::ptr_eq(&first, &assets.texture(SPRITE_SHEET).unwrap()));
+    }
+}
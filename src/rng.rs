@@ -0,0 +1,33 @@
+/// Small xorshift generator so runs can be reproduced from a seed
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // Xorshift gets stuck on zero so nudge it away
+        Rng {
+            state: if seed == 0 { 0x9E3779B97F4A7C15 } else { seed }
+        }
+    }
+
+    /// The whole state, `Rng::new` with it carries on the same sequence
+    pub fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 32) as u32
+    }
+
+    /// Random number in 0..max
+    pub fn next_range(&mut self, max: u32) -> u32 {
+        if max == 0 {
+            return 0;
+        }
+        self.next_u32() % max
+    }
+}
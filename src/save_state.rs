@@ -0,0 +1,254 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use ecs::Entity;
+use error::GameError;
+use level_object::LevelType;
+use map_format;
+use player::{self, MoveDirection};
+use units;
+
+const SAVE_STATE_HEADER: &'static str = "RUSTMAN-SAVE 2";
+
+/// Where an entity's mover was. Offsets are in tiles so the state loads at any window size.
+pub struct MoverState {
+    pub entity: Entity,
+    pub tile: (i32, i32),
+    pub offset: (f32, f32),
+    pub direction: MoveDirection,
+    pub desired_direction: MoveDirection,
+    pub rotation: f32,
+    /// Tiles per millisecond
+    pub speed: f32
+}
+
+/// Everything needed to carry on a game exactly where it was left.
+/// The format is the header line, then `<name> <values>` lines, with one `tiles` line per row of the maze.
+pub struct SaveState {
+    pub level_number: u32,
+    pub points: u32,
+    pub lives: u32,
+    pub extra_life_awarded: bool,
+    pub rng_state: u64,
+    pub frightened_time: units::DT,
+    pub initial_pellet_count: usize,
+    /// Indexed by x then y, like the level
+    pub tiles: Vec<Vec<LevelType>>,
+    pub movers: Vec<MoverState>,
+    /// Where each ghost the script spawned starts
+    pub scripted_ghosts: Vec<(i32, i32)>,
+    /// Speeds the script set, in tiles per millisecond
+    pub speed_overrides: Vec<(Entity, f32)>
+}
+
+impl SaveState {
+    // Filled in line by line as a save state loads
+    fn empty() -> SaveState {
+        SaveState {
+            level_number: 1,
+            points: 0,
+            lives: 0,
+            extra_life_awarded: false,
+            rng_state: 0,
+            frightened_time: 0.0,
+            initial_pellet_count: 0,
+            tiles: vec![],
+            movers: vec![],
+            scripted_ghosts: vec![],
+            speed_overrides: vec![]
+        }
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), GameError> {
+        let written = File::create(path).and_then(|mut file| file.write_all(self.to_text().as_bytes()));
+        written.map_err(|error| GameError::Io { path: PathBuf::from(path), error: error })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\nlevel {}\npoints {}\nlives {}\nextra_life {}\nrng {}\nfrightened {}\npellets_at_start {}\n",
+                               SAVE_STATE_HEADER, self.level_number, self.points, self.lives, self.extra_life_awarded as u8,
+                               self.rng_state, self.frightened_time, self.initial_pellet_count);
+
+        let height = self.tiles.first().map(|column| column.len()).unwrap_or(0);
+        for y in 0..height {
+            let row: String = self.tiles.iter().map(|column| map_format::level_type_to_char(&column[y])).collect();
+            // Quoted so rows of empty space survive trimming
+            text.push_str(&format!("tiles \"{}\"\n", row));
+        }
+        for mover in self.movers.iter() {
+            text.push_str(&format!("mover {} {} {} {} {} {} {} {} {}\n", mover.entity, mover.tile.0, mover.tile.1,
+                                   mover.offset.0, mover.offset.1, direction_name(&mover.direction),
+                                   direction_name(&mover.desired_direction), mover.rotation, mover.speed));
+        }
+        for &(x, y) in self.scripted_ghosts.iter() {
+            text.push_str(&format!("scripted_ghost {} {}\n", x, y));
+        }
+        for &(entity, speed) in self.speed_overrides.iter() {
+            text.push_str(&format!("speed_override {} {}\n", entity, speed));
+        }
+        return text;
+    }
+
+    pub fn load(path: &str) -> Result<SaveState, GameError> {
+        let invalid = || GameError::InvalidResource { path: PathBuf::from(path) };
+        let file = File::open(path).map_err(|error| GameError::Io { path: PathBuf::from(path), error: error })?;
+        let mut lines = BufReader::new(file).lines().map(|line| line.unwrap_or(String::new()));
+
+        if lines.next().as_deref() != Some(SAVE_STATE_HEADER) {
+            return Err(invalid());
+        }
+
+        let mut state = SaveState::empty();
+        let mut rows: Vec<Vec<LevelType>> = vec![];
+
+        for line in lines {
+            let mut words = line.splitn(2, ' ');
+            let (name, value) = match (words.next(), words.next()) {
+                (Some(name), Some(value)) => (name, value),
+                (Some(""), None) => continue,
+                _ => return Err(invalid())
+            };
+            match name {
+                "level" => state.level_number = value.parse().map_err(|_| invalid())?,
+                "points" => state.points = value.parse().map_err(|_| invalid())?,
+                "lives" => state.lives = value.parse().map_err(|_| invalid())?,
+                "extra_life" => state.extra_life_awarded = value == "1",
+                "rng" => state.rng_state = value.parse().map_err(|_| invalid())?,
+                "frightened" => state.frightened_time = value.parse().map_err(|_| invalid())?,
+                "pellets_at_start" => state.initial_pellet_count = value.parse().map_err(|_| invalid())?,
+                "tiles" => {
+                    let row = value.trim_matches('"');
+                    rows.push(row.chars().map(map_format::char_to_level_type).collect());
+                },
+                "mover" => state.movers.push(parse_mover(value).ok_or(invalid())?),
+                "scripted_ghost" => state.scripted_ghosts.push(parse_pair(value).ok_or(invalid())?),
+                "speed_override" => state.speed_overrides.push(parse_pair(value).ok_or(invalid())?),
+                _ => return Err(invalid())
+            }
+        }
+
+        // Rows are stored top to bottom, the level wants columns
+        let width = rows.first().map(|row| row.len()).unwrap_or(0);
+        if rows.iter().any(|row| row.len() != width) {
+            return Err(invalid());
+        }
+        state.tiles = (0..width).map(|x| rows.iter().map(|row| row[x].clone()).collect()).collect();
+        Ok(state)
+    }
+}
+
+fn parse_mover(value: &str) -> Option<MoverState> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    if words.len() != 9 {
+        return None;
+    }
+    Some(MoverState {
+        entity: words[0].parse().ok()?,
+        tile: (words[1].parse().ok()?, words[2].parse().ok()?),
+        offset: (words[3].parse().ok()?, words[4].parse().ok()?),
+        direction: player::parse_action(words[5])?,
+        desired_direction: player::parse_action(words[6])?,
+        rotation: words[7].parse().ok()?,
+        speed: words[8].parse().ok()?
+    })
+}
+
+// Two values separated by a space
+fn parse_pair<A: FromStr, B: FromStr>(value: &str) -> Option<(A, B)> {
+    let mut words = value.split_whitespace();
+    let pair = (words.next()?.parse().ok()?, words.next()?.parse().ok()?);
+    if words.next().is_some() {
+        return None;
+    }
+    Some(pair)
+}
+
+fn direction_name(direction: &MoveDirection) -> String {
+    format!("{:?}", direction).to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use game::{self, Game};
+    use game_time::GameTime;
+    use test_support;
+
+    fn temp_path(name: &str) -> String {
+        env::temp_dir().join(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn keeps_what_the_script_changed() {
+        let mut state = SaveState::empty();
+        state.tiles = vec![vec![LevelType::WALL, LevelType::PELLET]];
+        state.movers.push(MoverState { entity: 2, tile: (0, 1), offset: (0.25, 0.0), direction: MoveDirection::LEFT,
+                                       desired_direction: MoveDirection::NONE, rotation: 180.0, speed: 0.005 });
+        state.scripted_ghosts.push((3, 4));
+        state.speed_overrides.push((2, 0.0125));
+
+        let path = temp_path("rustman-save-state-test.txt");
+        state.write(&path).unwrap();
+        let loaded = SaveState::load(&path).unwrap();
+        assert_eq!(loaded.to_text(), state.to_text());
+        assert_eq!(loaded.movers[0].speed, 0.005);
+        assert_eq!(loaded.scripted_ghosts, vec![(3, 4)]);
+        assert_eq!(loaded.speed_overrides, vec![(2, 0.0125)]);
+    }
+
+    #[test]
+    fn write_failures_are_io_errors() {
+        let state = SaveState::empty();
+        match state.write(&env::temp_dir().to_string_lossy()) {
+            Err(GameError::Io { .. }) => { },
+            _ => panic!("writing over a directory should be an I/O error")
+        }
+    }
+
+    // Turns often, so the movers keep changing direction on both sides of the save
+    fn action_for(tick: u32) -> MoveDirection {
+        match (tick / 25) % 4 {
+            0 => MoveDirection::RIGHT,
+            1 => MoveDirection::DOWN,
+            2 => MoveDirection::LEFT,
+            _ => MoveDirection::UP
+        }
+    }
+
+    #[test]
+    fn a_loaded_game_carries_on_exactly_like_the_saved_one() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["##########",
+                                              "#P.o.....#",
+                                              "#.##.###.#",
+                                              "#........#",
+                                              "##########"]);
+        let mut saved = Game::new(&image, &sprite_sheet, 5);
+        let mut saved_time = GameTime::new();
+        for tick in 0..30 {
+            saved_time.advance(game::STEP_MS);
+            saved.update(action_for(tick), &saved_time, &mut ());
+        }
+        assert!(saved.is_frightened());
+
+        let path = temp_path("rustman-save-state-game-test.txt");
+        saved.get_save_state().write(&path).unwrap();
+        let mut loaded = Game::new(&image, &sprite_sheet, 99);
+        loaded.load_save_state(&SaveState::load(&path).unwrap()).unwrap();
+        assert_eq!(loaded.get_save_state().to_text(), saved.get_save_state().to_text());
+
+        let mut loaded_time = GameTime::new();
+        loaded_time.ticks = saved_time.ticks;
+        for tick in 30..150 {
+            saved_time.advance(game::STEP_MS);
+            saved.update(action_for(tick), &saved_time, &mut ());
+            loaded_time.advance(game::STEP_MS);
+            loaded.update(action_for(tick), &loaded_time, &mut ());
+            assert_eq!(loaded.get_save_state().to_text(), saved.get_save_state().to_text(), "the games split at tick {}", tick);
+        }
+        assert_eq!(loaded.rng.get_state(), saved.rng.get_state());
+    }
+}
@@ -0,0 +1,77 @@
+use events::{EventListener, GameEvent};
+
+const PELLET_POINTS: u32 = 10;
+const POWERPELLET_POINTS: u32 = 50;
+const EXTRA_LIFE_POINTS: u32 = 10000;
+pub const STARTING_LIVES: u32 = 3;
+
+pub struct Score {
+    pub points: u32,
+    pub lives: u32,
+    pub extra_life_awarded: bool
+}
+
+impl Score {
+    pub fn new() -> Score {
+        Score {
+            points: 0,
+            lives: STARTING_LIVES,
+            extra_life_awarded: false
+        }
+    }
+
+    /// Returns true once, the first time the score passes the extra life threshold
+    pub fn check_extra_life(&mut self) -> bool {
+        if self.extra_life_awarded || self.points < EXTRA_LIFE_POINTS {
+            return false;
+        }
+        self.extra_life_awarded = true;
+        return true;
+    }
+}
+
+impl EventListener for Score {
+    fn on_event(&mut self, event: &GameEvent) {
+        match *event {
+            GameEvent::PelletEaten { .. } => self.points += PELLET_POINTS,
+            GameEvent::PowerPelletEaten { .. } => self.points += POWERPELLET_POINTS,
+            GameEvent::GhostEaten { points, .. } => self.points += points,
+            GameEvent::PlayerDied => self.lives = self.lives.saturating_sub(1),
+            GameEvent::ExtraLife => self.lives += 1,
+            _ => { }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pellets_and_ghosts_score_points() {
+        let mut score = Score::new();
+        score.on_event(&GameEvent::PelletEaten { tile: (1, 1) });
+        score.on_event(&GameEvent::PowerPelletEaten { tile: (2, 1) });
+        score.on_event(&GameEvent::GhostEaten { tile: (3, 1), points: 200 });
+        assert_eq!(score.points, PELLET_POINTS + POWERPELLET_POINTS + 200);
+    }
+
+    #[test]
+    fn dying_never_takes_lives_below_zero() {
+        let mut score = Score::new();
+        for _ in 0..STARTING_LIVES + 1 {
+            score.on_event(&GameEvent::PlayerDied);
+        }
+        assert_eq!(score.lives, 0);
+    }
+
+    #[test]
+    fn the_extra_life_is_only_awarded_once() {
+        let mut score = Score::new();
+        assert!(!score.check_extra_life());
+
+        score.points = EXTRA_LIFE_POINTS;
+        assert!(score.check_extra_life());
+        assert!(!score.check_extra_life());
+    }
+}
@@ -0,0 +1,176 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use sfml::graphics::{Image, RenderTexture, RenderTarget, View, FloatRect, Color};
+use sfml::traits::Drawable;
+
+use bot::Bot;
+use game::{self, Game};
+use game_time::GameTime;
+use sprite_sheet::SpriteSheet;
+use error::GameError;
+
+// Channel difference allowed before two pixels count as different
+const GOLDEN_TOLERANCE: u8 = 8;
+
+/// Draws into an existing render texture, with `world_rect` stretched over the whole texture
+pub fn render_to<D: Drawable>(render_texture: &mut RenderTexture, drawable: &D, world_rect: &FloatRect) {
+    render_texture.set_view(&View::new_from_rect(world_rect).unwrap());
+    render_texture.clear(&Color::black());
+    render_texture.draw(drawable);
+    render_texture.display();
+}
+
+pub fn capture_to<D: Drawable>(render_texture: &mut RenderTexture, drawable: &D, world_rect: &FloatRect) -> Option<Image> {
+    render_to(render_texture, drawable, world_rect);
+    render_texture.get_texture().and_then(|texture| texture.copy_to_image())
+}
+
+pub fn capture<D: Drawable>(drawable: &D, world_rect: &FloatRect, width: u32, height: u32) -> Option<Image> {
+    match RenderTexture::new(width, height, false) {
+        Some(mut render_texture) => capture_to(&mut render_texture, drawable, world_rect),
+        None => None
+    }
+}
+
+/// Renders the game at the default window resolution
+pub fn capture_game(game: &Game) -> Option<Image> {
+    capture_game_at(game, super::NATIVE_TILE_SIZE * super::SCREEN_SCALE)
+}
+
+/// Renders the game with every tile `pixels_per_tile` pixels across
+pub fn capture_game_at(game: &Game, pixels_per_tile: u32) -> Option<Image> {
+    capture(game, &game.level.get_world_rect(), game.level.size.x * pixels_per_tile, game.level.size.y * pixels_per_tile)
+}
+
+/// Saves a screenshot named after the current time, returns the file name on success
+pub fn save_screenshot(game: &Game) -> Option<String> {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0);
+    let file_name = format!("screenshot_{}.png", seconds);
+
+    match capture_game(game) {
+        Some(ref image) if image.save_to_file(&file_name) => Some(file_name),
+        _ => None
+    }
+}
+
+pub fn images_match(first: &Image, second: &Image, tolerance: u8) -> bool {
+    let size = first.get_size();
+    if size != second.get_size() {
+        return false;
+    }
+
+    let channel_matches = |a: u8, b: u8| (a as i32 - b as i32).abs() <= tolerance as i32;
+    for x in 0..size.x {
+        for y in 0..size.y {
+            let first_pixel = first.get_pixel(x, y);
+            let second_pixel = second.get_pixel(x, y);
+            if !channel_matches(first_pixel.red, second_pixel.red) ||
+               !channel_matches(first_pixel.green, second_pixel.green) ||
+               !channel_matches(first_pixel.blue, second_pixel.blue) ||
+               !channel_matches(first_pixel.alpha, second_pixel.alpha) {
+                return false;
+            }
+        }
+    }
+    return true;
+}
+
+/// Simulates `frames` fixed steps from seed 0 with the bot playing, then renders the frame
+pub fn render_frame(map_image: &Image, sprite_sheet: &SpriteSheet, frames: u32, pixels_per_tile: u32) -> Result<Image, GameError> {
+    let mut game = Game::new(map_image, sprite_sheet, 0);
+    let mut game_time = GameTime::new();
+    let mut bot = Bot::new();
+
+    for _ in 0..frames {
+        game_time.advance(game::STEP_MS);
+        let action = bot.get_action(game.get_player_mover(), &game.level, &game.get_threats());
+        game.update(action, &game_time, &mut ());
+    }
+
+    capture_game_at(&game, pixels_per_tile).ok_or(GameError::RenderTextureCreation { width: game.level.size.x * pixels_per_tile,
+                                                                                     height: game.level.size.y * pixels_per_tile })
+}
+
+/// Renders the frame after `frames` fixed steps to `out_path`, failing if it doesn't match the golden image when one is given
+pub fn render_frame_to_file(map_image: &Image, sprite_sheet: &SpriteSheet, frames: u32, out_path: &str, golden_path: Option<&str>) -> Result<(), GameError> {
+    let image = render_frame(map_image, sprite_sheet, frames, super::NATIVE_TILE_SIZE * super::SCREEN_SCALE)?;
+    if !image.save_to_file(out_path) {
+        return Err(GameError::ImageWrite { path: PathBuf::from(out_path) });
+    }
+
+    if let Some(golden_path) = golden_path {
+        let golden = Image::new_from_file(golden_path).ok_or(GameError::InvalidResource { path: PathBuf::from(golden_path) })?;
+        if !images_match(&image, &golden, GOLDEN_TOLERANCE) {
+            return Err(GameError::GoldenMismatch { path: PathBuf::from(golden_path), frame: frames });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+    use std::path::Path;
+
+    use super::*;
+    use map_format;
+    use resources::Assets;
+    use test_support;
+
+    // Checked in next to the map it was rendered from, set UPDATE_GOLDEN=1 to write it again after a deliberate change.
+    // It is the opening frame with one pixel for every sprite sheet texel, so each pixel samples the middle of a
+    // texel and no driver can round onto a neighbouring one.
+    const GOLDEN_PATH: &'static str = "res/golden/frame_0.png";
+    const GOLDEN_FRAMES: u32 = 0;
+    const GOLDEN_PIXELS_PER_TILE: u32 = 64;
+    // Long enough for the bot, the ghosts and the animations to have moved everything off the tile centres
+    const REPEAT_FRAMES: u32 = 120;
+
+    #[test]
+    fn images_match_within_the_tolerance() {
+        let first = Image::new_from_color(2, 2, &Color::new_rgb(100, 100, 100)).unwrap();
+        let close = Image::new_from_color(2, 2, &Color::new_rgb(100 + GOLDEN_TOLERANCE, 100, 100)).unwrap();
+        assert!(images_match(&first, &close, GOLDEN_TOLERANCE));
+
+        let mut different = Image::new_from_color(2, 2, &Color::new_rgb(100, 100, 100)).unwrap();
+        different.set_pixel(1, 1, &Color::new_rgb(100, 100 + GOLDEN_TOLERANCE + 1, 100));
+        assert!(!images_match(&first, &different, GOLDEN_TOLERANCE));
+    }
+
+    #[test]
+    fn images_of_different_sizes_never_match() {
+        let first = Image::new_from_color(2, 2, &Color::black()).unwrap();
+        let second = Image::new_from_color(2, 3, &Color::black()).unwrap();
+        assert!(!images_match(&first, &second, 255));
+    }
+
+    #[test]
+    fn rendered_frame_matches_the_golden_image() {
+        let assets = Assets::new().unwrap();
+        let map_image = map_format::load_map(&assets, super::super::MAP_PATH).unwrap();
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = render_frame(&map_image, &sprite_sheet, GOLDEN_FRAMES, GOLDEN_PIXELS_PER_TILE).unwrap();
+
+        if env::var("UPDATE_GOLDEN").is_ok() {
+            if let Some(directory) = Path::new(GOLDEN_PATH).parent() {
+                fs::create_dir_all(directory).unwrap();
+            }
+            assert!(image.save_to_file(GOLDEN_PATH), "could not write {}", GOLDEN_PATH);
+            return;
+        }
+        let golden = Image::new_from_file(GOLDEN_PATH).expect("the golden image is missing, run with UPDATE_GOLDEN=1 to create it");
+        assert!(images_match(&image, &golden, GOLDEN_TOLERANCE), "frame {} no longer matches {}", GOLDEN_FRAMES, GOLDEN_PATH);
+    }
+
+    #[test]
+    fn rendering_the_same_frame_twice_gives_the_same_image() {
+        let assets = Assets::new().unwrap();
+        let map_image = map_format::load_map(&assets, super::super::MAP_PATH).unwrap();
+        let sprite_sheet = test_support::sprite_sheet();
+        let pixels_per_tile = super::super::NATIVE_TILE_SIZE * super::super::SCREEN_SCALE;
+        let first = render_frame(&map_image, &sprite_sheet, REPEAT_FRAMES, pixels_per_tile).unwrap();
+        let second = render_frame(&map_image, &sprite_sheet, REPEAT_FRAMES, pixels_per_tile).unwrap();
+        assert!(images_match(&first, &second, 0), "frame {} rendered differently the second time", REPEAT_FRAMES);
+    }
+}
@@ -0,0 +1,244 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use rhai::{Engine, AST, Scope, Dynamic, Array, Map, CallFnOptions, FuncArgs};
+use rhai::module_resolvers::DummyModuleResolver;
+
+use ecs::Entity;
+use error::GameError;
+use level_object::LevelType;
+use player::MoveDirection;
+
+// Limits that stop a broken script from hanging the game or eating its memory
+const MAX_OPERATIONS: u64 = 100000;
+const MAX_CALL_LEVELS: usize = 32;
+const MAX_EXPRESSION_DEPTH: usize = 64;
+const MAX_STRING_SIZE: usize = 4096;
+const MAX_COLLECTION_SIZE: usize = 4096;
+// Fastest a script can make anything go, in tiles per second
+const MAX_SPEED: f64 = 20.0;
+
+/// What a script can see of something that moves
+pub struct ActorView {
+    pub entity: Entity,
+    pub tile: (i32, i32),
+    pub direction: MoveDirection,
+    /// Tiles per second
+    pub speed: f32,
+    pub ghost: bool,
+    /// The player steering it, None for ghosts the game steers
+    pub slot: Option<usize>
+}
+
+/// A copy of the game the script reads in its hooks, scripts never touch the game directly.
+/// The game keeps it up to date through `update_view`, copying only what changed.
+pub struct ScriptView {
+    pub points: u32,
+    pub lives: u32,
+    pub level_number: u32,
+    /// Indexed by x then y, like the level
+    pub tiles: Vec<Vec<LevelType>>,
+    pub actors: Vec<ActorView>
+}
+
+/// A change a script asked for, checked and carried out by the game once the hook returns
+pub enum ScriptCommand {
+    SpawnPickup((i32, i32), LevelType),
+    /// Tiles per millisecond, already clamped
+    SetSpeed(Entity, f32),
+    SpawnGhost((i32, i32))
+}
+
+struct Shared {
+    view: ScriptView,
+    commands: Vec<ScriptCommand>
+}
+
+/// Rules written in Rhai, for custom game modes without rebuilding the game.
+/// Hooks, all optional: `on_start()`, `on_tick(tick)`, `on_pellet_eaten(x, y, power)`,
+/// `on_ghost_collision(ghost, frightened)` returning false to ignore the collision, and `ghost_target(ghost, frightened)`
+/// returning `[x, y]` for the tile a game steered ghost heads for, or nothing to keep the usual target.
+/// Reading: `level_width()`, `level_height()`, `tile(x, y)`, `actors()`, `points()`, `lives()`, `level_number()`.
+/// Changing: `spawn_pickup(x, y, "pellet" | "power_pellet")`, `spawn_ghost(x, y)`, `set_speed(id, tiles_per_second)`.
+/// Inside hooks `this` is a map that keeps whatever the script stores in it between calls.
+pub struct Script {
+    pub path: String,
+    engine: Engine,
+    ast: AST,
+    scope: Scope<'static>,
+    state: Dynamic,
+    shared: Rc<RefCell<Shared>>,
+    /// Hooks the script defines, a hook is turned off after its first error so it can't flood the console
+    hooks: HashSet<String>
+}
+
+impl Script {
+    pub fn load(path: &str) -> Result<Script, GameError> {
+        let shared = Rc::new(RefCell::new(Shared {
+            view: ScriptView { points: 0, lives: 0, level_number: 0, tiles: vec![], actors: vec![] },
+            commands: vec![]
+        }));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        engine.set_max_call_levels(MAX_CALL_LEVELS);
+        engine.set_max_expr_depths(MAX_EXPRESSION_DEPTH, MAX_EXPRESSION_DEPTH);
+        engine.set_max_string_size(MAX_STRING_SIZE);
+        engine.set_max_array_size(MAX_COLLECTION_SIZE);
+        engine.set_max_map_size(MAX_COLLECTION_SIZE);
+        // No loading other files, and no running code built from strings
+        engine.set_module_resolver(DummyModuleResolver::new());
+        engine.disable_symbol("eval");
+        register_api(&mut engine, &shared);
+
+        let ast = engine.compile_file(PathBuf::from(path))
+                        .map_err(|error| GameError::Script { path: path.to_string(), message: error.to_string() })?;
+        let hooks = ast.iter_functions().map(|function| function.name.to_string()).collect();
+
+        Ok(Script {
+            path: path.to_string(),
+            engine: engine,
+            ast: ast,
+            scope: Scope::new(),
+            state: Dynamic::from_map(Map::new()),
+            shared: shared,
+            hooks: hooks
+        })
+    }
+
+    /// Changes what the script sees, call before running hooks
+    pub fn update_view<F: FnOnce(&mut ScriptView)>(&mut self, update: F) {
+        update(&mut self.shared.borrow_mut().view);
+    }
+
+    /// Everything the script asked for since the last call
+    pub fn take_commands(&mut self) -> Vec<ScriptCommand> {
+        self.shared.borrow_mut().commands.drain(..).collect()
+    }
+
+    /// Runs the script's top level statements and then `on_start`, once when the game starts
+    pub fn start(&mut self) {
+        if let Err(error) = self.engine.run_ast_with_scope(&mut self.scope, &self.ast) {
+            eprintln!("{}: {}", self.path, error);
+        }
+        self.call("on_start", ());
+    }
+
+    pub fn on_tick(&mut self, tick: u32) {
+        self.call("on_tick", (tick as i64,));
+    }
+
+    pub fn on_pellet_eaten(&mut self, tile: (i32, i32), power: bool) {
+        self.call("on_pellet_eaten", (tile.0 as i64, tile.1 as i64, power));
+    }
+
+    /// Whether the game should handle a ghost sharing a tile with Pac-Man as usual
+    pub fn on_ghost_collision(&mut self, ghost: Entity, frightened: bool) -> bool {
+        let result = self.call("on_ghost_collision", (ghost as i64, frightened));
+        return result.and_then(|result| result.as_bool().ok()).unwrap_or(true);
+    }
+
+    /// The tile the script wants a ghost to head for, if it picks one
+    pub fn get_ghost_target(&mut self, ghost: Entity, frightened: bool) -> Option<(i32, i32)> {
+        let target = self.call("ghost_target", (ghost as i64, frightened))?.try_cast::<Array>()?;
+        match (target.first().and_then(|x| x.as_int().ok()), target.get(1).and_then(|y| y.as_int().ok())) {
+            (Some(x), Some(y)) => Some((x as i32, y as i32)),
+            _ => None
+        }
+    }
+
+    fn call<A: FuncArgs>(&mut self, hook: &str, args: A) -> Option<Dynamic> {
+        if !self.hooks.contains(hook) {
+            return None;
+        }
+        // The top level already ran in `start`, running it again would repeat its set up
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut self.state);
+        match self.engine.call_fn_with_options::<Dynamic>(options, &mut self.scope, &self.ast, hook, args) {
+            Ok(result) => Some(result),
+            Err(error) => {
+                eprintln!("{}: {} in {}, turning it off", self.path, error, hook);
+                self.hooks.remove(hook);
+                None
+            }
+        }
+    }
+}
+
+fn register_api(engine: &mut Engine, shared: &Rc<RefCell<Shared>>) {
+    let view = shared.clone();
+    engine.register_fn("level_width", move || view.borrow().view.tiles.len() as i64);
+    let view = shared.clone();
+    engine.register_fn("level_height", move || {
+        view.borrow().view.tiles.first().map(|column| column.len()).unwrap_or(0) as i64
+    });
+    let view = shared.clone();
+    engine.register_fn("tile", move |x: i64, y: i64| -> String {
+        let shared = view.borrow();
+        let level_type = shared.view.tiles.get(x as usize).and_then(|column| column.get(y as usize));
+        match level_type {
+            Some(level_type) if x >= 0 && y >= 0 => tile_name(level_type).to_string(),
+            _ => String::new()
+        }
+    });
+    let view = shared.clone();
+    engine.register_fn("actors", move || -> Array {
+        view.borrow().view.actors.iter().map(|actor| {
+            let mut map = Map::new();
+            map.insert("id".into(), Dynamic::from(actor.entity as i64));
+            map.insert("x".into(), Dynamic::from(actor.tile.0 as i64));
+            map.insert("y".into(), Dynamic::from(actor.tile.1 as i64));
+            map.insert("direction".into(), Dynamic::from(format!("{:?}", actor.direction).to_lowercase()));
+            map.insert("speed".into(), Dynamic::from(actor.speed as f64));
+            map.insert("ghost".into(), Dynamic::from(actor.ghost));
+            map.insert("slot".into(), actor.slot.map(|slot| Dynamic::from(slot as i64)).unwrap_or(Dynamic::UNIT));
+            Dynamic::from_map(map)
+        }).collect()
+    });
+    let view = shared.clone();
+    engine.register_fn("points", move || view.borrow().view.points as i64);
+    let view = shared.clone();
+    engine.register_fn("lives", move || view.borrow().view.lives as i64);
+    let view = shared.clone();
+    engine.register_fn("level_number", move || view.borrow().view.level_number as i64);
+
+    // Changes are only queued here, the game decides whether they are allowed
+    let commands = shared.clone();
+    engine.register_fn("spawn_pickup", move |x: i64, y: i64, kind: &str| {
+        let level_type = match kind {
+            "pellet" => LevelType::PELLET,
+            "power_pellet" => LevelType::POWERPELLET,
+            _ => return
+        };
+        commands.borrow_mut().commands.push(ScriptCommand::SpawnPickup((x as i32, y as i32), level_type));
+    });
+    let commands = shared.clone();
+    engine.register_fn("spawn_ghost", move |x: i64, y: i64| {
+        commands.borrow_mut().commands.push(ScriptCommand::SpawnGhost((x as i32, y as i32)));
+    });
+    let commands = shared.clone();
+    engine.register_fn("set_speed", move |id: i64, tiles_per_second: f64| {
+        // NaN would get through the clamp and stop the mover for good
+        if tiles_per_second.is_nan() {
+            return;
+        }
+        let speed = tiles_per_second.clamp(0.0, MAX_SPEED) / 1000.0;
+        commands.borrow_mut().commands.push(ScriptCommand::SetSpeed(id as Entity, speed as f32));
+    });
+    // Whole numbers aren't turned into floats by themselves
+    let commands = shared.clone();
+    engine.register_fn("set_speed", move |id: i64, tiles_per_second: i64| {
+        let speed = (tiles_per_second as f64).clamp(0.0, MAX_SPEED) / 1000.0;
+        commands.borrow_mut().commands.push(ScriptCommand::SetSpeed(id as Entity, speed as f32));
+    });
+}
+
+fn tile_name(level_type: &LevelType) -> &'static str {
+    match *level_type {
+        LevelType::SPACE => "space",
+        LevelType::WALL => "wall",
+        LevelType::PELLET => "pellet",
+        LevelType::POWERPELLET => "power_pellet"
+    }
+}
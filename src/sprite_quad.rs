@@ -0,0 +1,46 @@
+use sfml::system::Vector2f;
+use sfml::graphics::{RenderTarget, VertexArray, PrimitiveType, Sprite, RenderStates};
+
+/// Draws any actor's sprite as a textured quad, one shared vertex array is refilled for each sprite
+pub struct SpriteQuad {
+    vertex_array: VertexArray
+}
+
+impl SpriteQuad {
+    pub fn new() -> SpriteQuad {
+        SpriteQuad {
+            vertex_array: VertexArray::new_init(PrimitiveType::Quads, 4).unwrap()
+        }
+    }
+
+    pub fn draw_sprite<RT: RenderTarget>(&self, sprite: &Sprite, target: &mut RT) {
+        let sprite_rect = sprite.get_local_bounds();
+        let texture_rect = sprite.get_texture_rect();
+
+        // Bottom left
+        self.vertex_array.get_vertex(0).position = Vector2f::new(sprite_rect.left, sprite_rect.top + sprite_rect.height);
+        self.vertex_array.get_vertex(0).tex_coords = Vector2f::new(texture_rect.left as f32,
+                                                                   (texture_rect.top + texture_rect.height) as f32);
+
+        // Top left
+        self.vertex_array.get_vertex(1).position = Vector2f::new(sprite_rect.left, sprite_rect.top);
+        self.vertex_array.get_vertex(1).tex_coords = Vector2f::new(texture_rect.left as f32,
+                                                                   texture_rect.top as f32);
+
+        // Top right
+        self.vertex_array.get_vertex(2).position = Vector2f::new(sprite_rect.left + sprite_rect.width, sprite_rect.top);
+        self.vertex_array.get_vertex(2).tex_coords = Vector2f::new((texture_rect.left + texture_rect.width) as f32,
+                                                                   texture_rect.top as f32);
+
+        // Bottom right
+        self.vertex_array.get_vertex(3).position = Vector2f::new(sprite_rect.left + sprite_rect.width,
+                                                                 sprite_rect.top + sprite_rect.height);
+        self.vertex_array.get_vertex(3).tex_coords = Vector2f::new((texture_rect.left + texture_rect.width) as f32,
+                                                                   (texture_rect.top + texture_rect.height) as f32);
+
+        let mut states = RenderStates::default();
+        states.texture = Some(sprite.get_texture().unwrap());
+        states.transform = sprite.get_transform();
+        target.draw_with_renderstates(&self.vertex_array, &mut states);
+    }
+}
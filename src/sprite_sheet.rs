@@ -1,30 +1,34 @@
 use std::collections::HashMap;
+use std::rc::Rc;
 use sfml::graphics::{IntRect, Texture, Sprite};
 
 use level_object::LevelType;
 use animation_sprite::AnimationSprite;
+use error::GameError;
+use resources::Assets;
 
 const SPRITE_SIZE: f32 = 64.0;
-const SPRITESHEET_PATH: &'static str = "res/sprites/game.png";
+pub const SPRITESHEET_PATH: &'static str = "res/sprites/game.png";
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum SpriteType {
-    PLAYER
+    PLAYER,
+    GHOST
 }
 
 pub struct SpriteSheet {
-    pub texture: Texture,
+    pub texture: Rc<Texture>,
     sprite_background_map: HashMap<LevelType, IntRect>,
     sprite_foreground_map: HashMap<SpriteType, Vec<IntRect>>
 }
 
 impl SpriteSheet {
-    pub fn new() -> SpriteSheet {
-        SpriteSheet {
+    pub fn new(assets: &mut Assets) -> Result<SpriteSheet, GameError> {
+        Ok(SpriteSheet {
             sprite_background_map: SpriteSheet::generate_background_map(),
             sprite_foreground_map: SpriteSheet::generate_foreground_map(),
-            texture: Texture::new_from_file(SPRITESHEET_PATH).unwrap()
-        }
+            texture: assets.texture(SPRITESHEET_PATH)?
+        })
     }
     
     fn generate_background_map() -> HashMap<LevelType, IntRect> {
@@ -35,6 +39,13 @@ impl SpriteSheet {
         generated_map.insert(LevelType::WALL, IntRect::new((SPRITE_SIZE * 0.0) as i32,
                                                            (SPRITE_SIZE * 0.0) as i32,
                                                             SPRITE_SIZE as i32, SPRITE_SIZE as i32));
+        // Power pellets share the pellet art, the level draws them bigger
+        generated_map.insert(LevelType::PELLET, IntRect::new((SPRITE_SIZE * 2.0) as i32,
+                                                             (SPRITE_SIZE * 0.0) as i32,
+                                                             SPRITE_SIZE as i32, SPRITE_SIZE as i32));
+        generated_map.insert(LevelType::POWERPELLET, IntRect::new((SPRITE_SIZE * 2.0) as i32,
+                                                                   (SPRITE_SIZE * 0.0) as i32,
+                                                                   SPRITE_SIZE as i32, SPRITE_SIZE as i32));
         return generated_map;
     }
     
@@ -51,7 +62,12 @@ impl SpriteSheet {
                                                (SPRITE_SIZE * 1.0) as i32,
                                                 SPRITE_SIZE as i32, SPRITE_SIZE as i32)];
         
+        // Placeholder art: there are no ghost frames in the sprite sheet yet, so ghosts are the closed mouth
+        // Pac-Man frame tinted when they are spawned. Swap in real frames here once they are drawn.
+        let ghost_sprites = vec![player_sprites[0]];
+
         generated_map.insert(SpriteType::PLAYER, player_sprites);
+        generated_map.insert(SpriteType::GHOST, ghost_sprites);
         return generated_map;
     }
     
@@ -73,7 +89,7 @@ impl SpriteSheet {
                 sprite.set_texture_rect(&rects[0]);
                 return AnimationSprite::new(sprite, rects.clone());
             }
-            &None => panic!("Unable to retrieve SpriteType::{:?}.")
+            &None => panic!("Unable to retrieve SpriteType::{:?}. Map's contents was: {:?}", sprite_type, self.sprite_foreground_map)
         }
     }
 }
@@ -0,0 +1,72 @@
+use ecs::World;
+use level::Level;
+use player::MoveDirection;
+use game_time::GameTime;
+
+/// Passes each player's action on to their mover
+pub fn control_system(world: &mut World) {
+    for (entity, control) in world.controls.iter_mut() {
+        if let Some(mover) = world.movers.get_mut(entity) {
+            mover.request_direction(control.action.clone());
+        }
+        control.action = MoveDirection::NONE;
+    }
+}
+
+/// Moves every grid mover, facing its transform the way it goes and animating its sprite only while it moves
+pub fn movement_system(world: &mut World, game_time: &GameTime, level: &Level) {
+    for (entity, mover) in world.movers.iter_mut() {
+        let previous_position = mover.get_world_position(level);
+        mover.update(game_time.delta_time, level);
+        let moved = previous_position != mover.get_world_position(level);
+
+        if let Some(transform) = world.transforms.get_mut(entity) {
+            match mover.direction {
+                MoveDirection::UP => transform.rotation = 270.0,
+                MoveDirection::DOWN => transform.rotation = 90.0,
+                MoveDirection::LEFT => transform.rotation = 180.0,
+                MoveDirection::RIGHT => transform.rotation = 0.0,
+                MoveDirection::NONE => { }
+            }
+        }
+        if let Some(sprite) = world.sprites.get_mut(entity) {
+            if moved {
+                sprite.start_animation();
+            } else {
+                sprite.stop_animation();
+            }
+        }
+    }
+}
+
+/// Copies mover positions into transforms, then transforms into sprites
+pub fn sync_system(world: &mut World, level: &Level) {
+    for (entity, mover) in world.movers.iter() {
+        if let Some(transform) = world.transforms.get_mut(entity) {
+            transform.position = mover.get_world_position(level);
+        }
+    }
+    for (entity, transform) in world.transforms.iter() {
+        if let Some(sprite) = world.sprites.get_mut(entity) {
+            sprite.sfml_sprite.set_position(&transform.position);
+            sprite.sfml_sprite.set_rotation(transform.rotation);
+        }
+    }
+}
+
+pub fn animation_system(world: &mut World, game_time: &GameTime) {
+    for (_, sprite) in world.sprites.iter_mut() {
+        sprite.update(game_time);
+    }
+}
+
+/// Keeps everything on the same spot of the maze after the tile size changed, `ratio` is the new size over the old
+pub fn rescale_system(world: &mut World, ratio: f32, level: &Level) {
+    for (_, mover) in world.movers.iter_mut() {
+        mover.offset = mover.offset * ratio;
+    }
+    for (_, sprite) in world.sprites.iter_mut() {
+        sprite.set_size(level.get_tile_size());
+    }
+    sync_system(world, level);
+}
@@ -0,0 +1,16 @@
+use sfml::graphics::Image;
+
+use resources::Assets;
+use sprite_sheet::SpriteSheet;
+use map_format;
+
+/// The real sprite sheet, levels need one for their tile sprites
+pub fn sprite_sheet() -> SpriteSheet {
+    let mut assets = Assets::new().expect("the tests run from the crate root");
+    SpriteSheet::new(&mut assets).expect("the sprite sheet loads")
+}
+
+/// A map image from rows of a text map, `#` for walls, `.` for pellets, `o` for power pellets and `P` for the spawn
+pub fn map_image(rows: &[&str]) -> Image {
+    map_format::text_to_image(&rows.join("\n")).expect("the rows make a map")
+}
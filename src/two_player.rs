@@ -0,0 +1,149 @@
+use game::Game;
+use input::{self, Bindings};
+use save_state::SaveState;
+use units;
+
+// How long the "PLAYER ONE" or "PLAYER TWO" screen shows before play carries on
+const READY_MS: units::DT = 2000.0;
+
+const PLAYER_NAMES: [&'static str; 2] = ["PLAYER ONE", "PLAYER TWO"];
+
+/// Two players taking turns like the arcade, swapping after each death. Each keeps their own score, lives,
+/// level and maze, so the game only ever holds whoever is playing and the other waits as a save state.
+pub struct TwoPlayer {
+    pub current: usize,
+    /// Player two steers with the arrow keys instead of sharing WASD
+    pub separate_controls: bool,
+    waiting: SaveState,
+    ready_time: units::DT
+}
+
+impl TwoPlayer {
+    /// Both players start from the game as it is now, player one goes first
+    pub fn new(game: &Game, separate_controls: bool) -> TwoPlayer {
+        TwoPlayer {
+            current: 0,
+            separate_controls: separate_controls,
+            waiting: game.get_save_state(),
+            ready_time: READY_MS
+        }
+    }
+
+    pub fn get_bindings(&self) -> &'static Bindings {
+        if self.separate_controls && self.current == 1 { &input::ARROWS } else { &input::WASD }
+    }
+
+    /// Hands over to the waiting player if they have lives left, otherwise the same player goes again.
+    /// Once neither has any lives the game is left over.
+    pub fn on_player_died(&mut self, game: &mut Game) {
+        if self.waiting.lives == 0 && game.is_game_over() {
+            self.ready_time = 0.0;
+            return;
+        }
+        if self.waiting.lives > 0 {
+            let playing = game.get_save_state();
+            if let Err(error) = game.load_save_state(&self.waiting) {
+                eprintln!("Could not swap players: {}", error);
+                return;
+            }
+            self.waiting = playing;
+            self.current = 1 - self.current;
+        }
+        game.reset_world();
+        self.ready_time = READY_MS;
+    }
+
+    /// Counts down the ready screen, the game shouldn't update while it shows
+    pub fn update(&mut self, delta_time: units::DT) {
+        self.ready_time = (self.ready_time - delta_time).max(0.0);
+    }
+
+    pub fn is_showing_ready(&self) -> bool {
+        self.ready_time > 0.0
+    }
+
+    pub fn get_ready_text(&self) -> &'static str {
+        PLAYER_NAMES[self.current]
+    }
+
+    /// Both scores for the HUD, player one first
+    pub fn get_scores_text(&self, game: &Game) -> String {
+        let (one, two) = if self.current == 0 {
+            (game.score.points, self.waiting.points)
+        } else {
+            (self.waiting.points, game.score.points)
+        };
+        format!("1UP {:>6}    2UP {:>6}", one, two)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support;
+    use game::STEP_MS;
+    use game_time::GameTime;
+    use events::GameEvent;
+    use player::MoveDirection;
+
+    const MAZE: [&'static str; 3] = ["#####",
+                                     "#P..#",
+                                     "#####"];
+
+    #[test]
+    fn swaps_to_the_waiting_player_with_lives_left() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&MAZE);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        let mut two_player = TwoPlayer::new(&game, false);
+
+        game.score.lives = 0;
+        two_player.on_player_died(&mut game);
+        assert_eq!(two_player.current, 1);
+        assert!(!game.is_game_over());
+        assert!(two_player.is_showing_ready());
+    }
+
+    #[test]
+    fn game_over_once_both_players_are_out_of_lives() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&MAZE);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        let mut two_player = TwoPlayer::new(&game, false);
+
+        two_player.waiting.lives = 0;
+        game.score.lives = 0;
+        two_player.on_player_died(&mut game);
+        assert_eq!(two_player.current, 0);
+        assert!(game.is_game_over());
+        assert!(!two_player.is_showing_ready());
+    }
+
+    #[test]
+    fn a_ghost_catching_player_one_hands_over_to_player_two() {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(&["######",
+                                              "#P   #",
+                                              "######"]);
+        let mut game = Game::new(&image, &sprite_sheet, 0);
+        game.ghost_count = 1;
+        game.reset_world();
+        let mut two_player = TwoPlayer::new(&game, false);
+
+        let mut game_time = GameTime::new();
+        let mut caught = false;
+        for _ in 0..300 {
+            game_time.advance(STEP_MS);
+            game.update(MoveDirection::RIGHT, &game_time, &mut ());
+            if game.events.contains(&GameEvent::PlayerDied) {
+                two_player.on_player_died(&mut game);
+                caught = true;
+                break;
+            }
+        }
+        assert!(caught);
+        assert_eq!(two_player.current, 1);
+        assert_eq!(two_player.waiting.lives + 1, game.score.lives);
+        assert!(two_player.is_showing_ready());
+    }
+}
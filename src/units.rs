@@ -1,2 +1,3 @@
 pub type MS = i32;
 pub type DT = f32;
+pub type US = i64;
\ No newline at end of file
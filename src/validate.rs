@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+
+use level::Level;
+use level_object::LevelType;
+
+// Stop listing unreachable pellets after this many so the report stays readable
+const MAX_REPORTED_TILES: usize = 5;
+
+/// Checks a level is playable, returning a description of every problem found
+pub fn validate_level(level: &Level) -> Vec<String> {
+    let mut problems = vec![];
+    let width = level.size.x as i32;
+    let height = level.size.y as i32;
+
+    if width == 0 || height == 0 {
+        problems.push("The map is empty".to_string());
+        return problems;
+    }
+
+    if level.get_pellet_count() == 0 {
+        problems.push("The map has no pellets to eat".to_string());
+    }
+
+    let mut open_edges = vec![];
+    for x in 0..width {
+        for y in 0..height {
+            let on_edge = x == 0 || y == 0 || x == width - 1 || y == height - 1;
+            if on_edge && level.is_walkable(x, y) {
+                open_edges.push((x, y));
+            }
+        }
+    }
+    if !open_edges.is_empty() {
+        problems.push(format!("The player can walk off the map at {}", format_tiles(&open_edges)));
+    }
+
+    // Without a spawn tile the player starts in the middle of the map, see Game::get_spawn_tile
+    let world_rect = level.get_world_rect();
+    let spawn = level.get_player_spawn()
+                     .unwrap_or(level.world_to_tile(world_rect.width / 2.0, world_rect.height / 2.0));
+    if !level.is_walkable(spawn.0, spawn.1) {
+        problems.push(format!("The player starts inside a wall at {:?}", spawn));
+        return problems;
+    }
+
+    let reachable = find_reachable(level, spawn);
+    let mut unreachable_pellets = vec![];
+    for x in 0..width {
+        for y in 0..height {
+            let level_type = &level.get_tile(x, y).level_type;
+            let is_pellet = *level_type == LevelType::PELLET || *level_type == LevelType::POWERPELLET;
+            if is_pellet && !reachable[(x + y * width) as usize] {
+                unreachable_pellets.push((x, y));
+            }
+        }
+    }
+    if !unreachable_pellets.is_empty() {
+        problems.push(format!("{} pellets can't be reached from the start, at {}",
+                              unreachable_pellets.len(), format_tiles(&unreachable_pellets)));
+    }
+    return problems;
+}
+
+// Flood fill through walkable tiles, indexed by x + y * width
+fn find_reachable(level: &Level, start: (i32, i32)) -> Vec<bool> {
+    let width = level.size.x as i32;
+    let height = level.size.y as i32;
+    let mut reachable = vec![false; (width * height) as usize];
+    let mut queue = VecDeque::new();
+
+    reachable[(start.0 + start.1 * width) as usize] = true;
+    queue.push_back(start);
+
+    while let Some((x, y)) = queue.pop_front() {
+        for &(next_x, next_y) in [(x, y - 1), (x, y + 1), (x - 1, y), (x + 1, y)].iter() {
+            if next_x < 0 || next_y < 0 || next_x >= width || next_y >= height {
+                continue;
+            }
+            let index = (next_x + next_y * width) as usize;
+            if !reachable[index] && level.is_walkable(next_x, next_y) {
+                reachable[index] = true;
+                queue.push_back((next_x, next_y));
+            }
+        }
+    }
+    return reachable;
+}
+
+fn format_tiles(tiles: &[(i32, i32)]) -> String {
+    let mut listed: Vec<String> = tiles.iter().take(MAX_REPORTED_TILES).map(|tile| format!("{:?}", tile)).collect();
+    if tiles.len() > MAX_REPORTED_TILES {
+        listed.push(format!("and {} more", tiles.len() - MAX_REPORTED_TILES));
+    }
+    listed.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use test_support;
+
+    fn problems(rows: &[&str]) -> Vec<String> {
+        let sprite_sheet = test_support::sprite_sheet();
+        let image = test_support::map_image(rows);
+        let level = Level::new_with_image(&image, &sprite_sheet);
+        validate_level(&level)
+    }
+
+    #[test]
+    fn a_closed_maze_with_reachable_pellets_is_fine() {
+        assert!(problems(&["#####",
+                           "#P.o#",
+                           "#####"]).is_empty());
+    }
+
+    #[test]
+    fn open_edges_are_reported() {
+        let problems = problems(&["#####",
+                                  "#P.. ",
+                                  "#####"]);
+        assert_eq!(problems, vec!["The player can walk off the map at (4, 1)".to_string()]);
+    }
+
+    #[test]
+    fn unreachable_pellets_are_reported() {
+        let problems = problems(&["#######",
+                                  "#P.#..#",
+                                  "#######"]);
+        assert_eq!(problems, vec!["2 pellets can't be reached from the start, at (4, 1), (5, 1)".to_string()]);
+    }
+
+    #[test]
+    fn a_spawn_inside_a_wall_is_reported() {
+        // Without a spawn tile the player starts in the middle, here (2, 1)
+        let problems = problems(&["#####",
+                                  "#.###",
+                                  "#####"]);
+        assert_eq!(problems, vec!["The player starts inside a wall at (2, 1)".to_string()]);
+    }
+
+    #[test]
+    fn a_maze_without_pellets_is_reported() {
+        let problems = problems(&["####",
+                                  "#P #",
+                                  "####"]);
+        assert_eq!(problems, vec!["The map has no pellets to eat".to_string()]);
+    }
+}